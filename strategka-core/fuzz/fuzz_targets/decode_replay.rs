@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use strategka_core::{DecodeLimits, Replay};
+use strategka_core_fuzz::{decode_all, RichWorld, SimpleWorld};
+
+fuzz_target!(|data: &[u8]| {
+    decode_all::<SimpleWorld>(data);
+    decode_all::<RichWorld>(data);
+    // Decoder must not depend on limits to stay memory safe
+    let _ = Replay::<RichWorld>::decode_with_limits(data, DecodeLimits::unlimited());
+});
@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use strategka_core::{DecodeLimits, SaveGame};
+use strategka_core_fuzz::{RichWorld, SimpleWorld};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = SaveGame::<SimpleWorld>::decode_with_limits(data, DecodeLimits::unlimited());
+    let _ = SaveGame::<RichWorld>::decode_with_limits(data, DecodeLimits::unlimited());
+});
@@ -0,0 +1,31 @@
+#![no_main]
+//! Structure aware fuzzing: valid replays from `Replay::encode` are corrupted
+//! with a few mutations, so the decoder gets past the header checks.
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use strategka_core::Replay;
+use strategka_core_fuzz::{decode_all, ArbitraryReplay, Mutation, RichWorld};
+
+#[derive(Debug, Arbitrary)]
+struct Case {
+    replay: ArbitraryReplay<RichWorld>,
+    mutations: Vec<Mutation>,
+    through_file: bool,
+}
+
+fuzz_target!(|case: Case| {
+    let replay = case.replay.into_replay();
+    let mut bytes = vec![];
+    replay.encode(&mut bytes).expect("encoded");
+    if case.mutations.is_empty() {
+        assert_eq!(Replay::decode(&bytes).expect("decoded"), replay);
+    }
+    for mutation in case.mutations.iter() {
+        mutation.apply(&mut bytes);
+    }
+    decode_all::<RichWorld>(&bytes);
+    if case.through_file {
+        let file = temp_file::with_contents(&bytes);
+        let _ = Replay::<RichWorld>::load(file.path());
+    }
+});
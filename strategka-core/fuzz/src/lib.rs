@@ -0,0 +1,154 @@
+//! Test worlds and structure aware mutations shared by fuzz targets
+use arbitrary::Arbitrary;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Debug};
+use strategka_core::{DecodeLimits, Replay, SaveGame, World};
+
+/// World with plain fields and simple inputs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Arbitrary)]
+pub struct SimpleWorld {
+    pub counter: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Arbitrary)]
+pub enum SimpleInput {
+    Add(u32),
+    Sub(u32),
+}
+
+impl World for SimpleWorld {
+    type Input = SimpleInput;
+
+    fn magic_bytes() -> [u8; 4] {
+        *b"FZW1"
+    }
+
+    fn current_version() -> u32 {
+        1
+    }
+}
+
+/// World with nested containers that make CBOR decoder work harder
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Arbitrary)]
+pub struct RichWorld {
+    pub name: String,
+    pub units: Vec<(i64, i64)>,
+    pub tags: BTreeMap<String, Vec<u8>>,
+    pub selected: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Arbitrary)]
+pub enum RichInput {
+    Select(Option<u16>),
+    Move { unit: u32, x: i64, y: i64 },
+    Chat(String),
+    Script(Vec<(u32, Option<String>)>),
+}
+
+impl World for RichWorld {
+    type Input = RichInput;
+
+    fn magic_bytes() -> [u8; 4] {
+        *b"FZW2"
+    }
+
+    fn current_version() -> u32 {
+        1
+    }
+}
+
+/// Replay built from fuzzer data. Turns are made strictly increasing and the
+/// rate is at least one, so the encoding is always valid before mutations.
+#[derive(Debug, Clone, Arbitrary)]
+pub struct ArbitraryReplay<W: World>
+where
+    W::Input: for<'a> Arbitrary<'a>,
+{
+    pub rate: u32,
+    pub initial: W,
+    pub extra_turns: u8,
+    pub turns: Vec<(u8, Vec<W::Input>)>,
+}
+
+impl<W> ArbitraryReplay<W>
+where
+    W: World + Default + Clone + Serialize + DeserializeOwned,
+    W::Input: for<'a> Arbitrary<'a>,
+{
+    pub fn into_replay(self) -> Replay<W> {
+        let mut replay = Replay::new(&self.initial, self.rate.max(1));
+        let mut turn = 0;
+        for (gap, inputs) in self.turns {
+            replay.record(turn, &inputs).expect("increasing turns");
+            turn += gap as u64 + 1;
+        }
+        replay.total_turns += self.extra_turns as u64;
+        replay
+    }
+}
+
+/// Corruption of encoded bytes
+#[derive(Debug, Clone, Arbitrary)]
+pub enum Mutation {
+    /// Xor byte at position
+    Flip(u16, u8),
+    /// Cut the encoding at position
+    Truncate(u16),
+    /// Insert bytes at position
+    Insert(u16, Vec<u8>),
+    /// Remove bytes starting at position
+    Remove(u16, u8),
+    /// Overwrite 8 bytes at position, hits length prefixes and turn numbers
+    SetU64(u16, u64),
+}
+
+impl Mutation {
+    pub fn apply(&self, bytes: &mut Vec<u8>) {
+        if bytes.is_empty() {
+            return;
+        }
+        let at = |pos: &u16| *pos as usize % bytes.len();
+        match self {
+            Mutation::Flip(pos, mask) => {
+                let i = at(pos);
+                bytes[i] ^= mask;
+            }
+            Mutation::Truncate(pos) => bytes.truncate(at(pos)),
+            Mutation::Insert(pos, values) => {
+                let i = at(pos);
+                bytes.splice(i..i, values.iter().copied());
+            }
+            Mutation::Remove(pos, len) => {
+                let i = at(pos);
+                let end = (i + *len as usize).min(bytes.len());
+                bytes.drain(i..end);
+            }
+            Mutation::SetU64(pos, value) => {
+                let i = at(pos);
+                let end = (i + 8).min(bytes.len());
+                bytes[i..end].copy_from_slice(&value.to_be_bytes()[..end - i]);
+            }
+        }
+    }
+}
+
+/// Decode bytes with all decoders of the world. Panics only if decoded value
+/// doesn't survive encoding round trip.
+pub fn decode_all<W>(bytes: &[u8])
+where
+    W: World + Default + Clone + PartialEq + Debug + Serialize + DeserializeOwned,
+{
+    let limits = DecodeLimits::default();
+    if let Ok(replay) = Replay::<W>::decode_with_limits(bytes, limits) {
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+        let decoded = Replay::<W>::decode_with_limits(&buffer, limits).expect("decoded");
+        assert_eq!(replay, decoded);
+    }
+    if let Ok(save) = SaveGame::<W>::decode_with_limits(bytes, limits) {
+        let mut buffer = vec![];
+        save.encode(&mut buffer).expect("encoded");
+        let decoded = SaveGame::<W>::decode_with_limits(&buffer, limits).expect("decoded");
+        assert_eq!(save, decoded);
+    }
+}
@@ -0,0 +1,529 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
+};
+
+/// Fixed point number with 32 integer bits and 32 fractional bits.
+///
+/// All operations are done with integer arithmetic, so results are bit
+/// identical on all platforms. Use the type instead of floats inside
+/// simulation state to keep replays and lockstep deterministic.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Fixed(i64);
+
+const FRAC_BITS: u32 = 32;
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << FRAC_BITS);
+    pub const HALF: Fixed = Fixed(1 << (FRAC_BITS - 1));
+    pub const PI: Fixed = Fixed(13493037705);
+    pub const FRAC_PI_2: Fixed = Fixed(6746518852);
+    pub const TAU: Fixed = Fixed(26986075409);
+    /// Smallest positive value
+    pub const EPSILON: Fixed = Fixed(1);
+    pub const MIN: Fixed = Fixed(i64::MIN);
+    pub const MAX: Fixed = Fixed(i64::MAX);
+
+    /// Create from underlying representation
+    pub const fn from_bits(bits: i64) -> Self {
+        Fixed(bits)
+    }
+
+    /// Get underlying representation
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    pub const fn from_int(value: i32) -> Self {
+        Fixed((value as i64) << FRAC_BITS)
+    }
+
+    /// Make number `numerator / denominator` without intermediate floats
+    pub const fn from_ratio(numerator: i64, denominator: i64) -> Self {
+        Fixed((((numerator as i128) << FRAC_BITS) / denominator as i128) as i64)
+    }
+
+    /// Conversion from float. The result is deterministic for the same float,
+    /// but avoid doing float math before the conversion in simulation code.
+    pub fn from_f32(value: f32) -> Self {
+        Fixed((value as f64 * (1u64 << FRAC_BITS) as f64) as i64)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / (1u64 << FRAC_BITS) as f64
+    }
+
+    /// Largest integer less than or equal to the number
+    pub const fn to_int(self) -> i32 {
+        (self.0 >> FRAC_BITS) as i32
+    }
+
+    pub const fn floor(self) -> Self {
+        Fixed(self.0 & !((1 << FRAC_BITS) - 1))
+    }
+
+    pub const fn ceil(self) -> Self {
+        Fixed(self.0 + ((1 << FRAC_BITS) - 1)).floor()
+    }
+
+    pub const fn round(self) -> Self {
+        Fixed(self.0 + Self::HALF.0).floor()
+    }
+
+    /// Fractional part of the number, always non negative
+    pub const fn fract(self) -> Self {
+        Fixed(self.0 & ((1 << FRAC_BITS) - 1))
+    }
+
+    pub const fn abs(self) -> Self {
+        Fixed(self.0.abs())
+    }
+
+    pub const fn signum(self) -> Self {
+        Fixed::from_int(self.0.signum() as i32)
+    }
+
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// Square root of the number. Negative numbers have root 0.
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Fixed::ZERO;
+        }
+        Fixed(isqrt((self.0 as u128) << FRAC_BITS) as i64)
+    }
+
+    /// Sine of the angle in radians. Absolute error is less than `1e-5`.
+    pub fn sin(self) -> Self {
+        // Reduce to [-PI, PI]
+        let mut x = Fixed(self.0.rem_euclid(Self::TAU.0));
+        if x > Self::PI {
+            x -= Self::TAU;
+        }
+        // Reduce to [-PI/2, PI/2] with sin(x) = sin(PI - x)
+        if x > Self::FRAC_PI_2 {
+            x = Self::PI - x;
+        } else if x < -Self::FRAC_PI_2 {
+            x = -Self::PI - x;
+        }
+        // Taylor series up to x^11
+        let x2 = x * x;
+        let mut term = x;
+        let mut result = x;
+        for k in 1..6 {
+            term = -term * x2 / Fixed::from_int((2 * k) * (2 * k + 1));
+            result += term;
+        }
+        result
+    }
+
+    /// Cosine of the angle in radians. Absolute error is less than `1e-5`.
+    pub fn cos(self) -> Self {
+        (self + Self::FRAC_PI_2).sin()
+    }
+
+    /// Tangent of the angle in radians
+    pub fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+
+    /// Angle of the point `(x, y)` in radians in range `[-PI, PI]`. Absolute
+    /// error is less than `1e-4`.
+    pub fn atan2(self, x: Fixed) -> Self {
+        let (ax, ay) = (x.abs(), self.abs());
+        if ax == Fixed::ZERO && ay == Fixed::ZERO {
+            return Fixed::ZERO;
+        }
+        let mut angle = if ax >= ay {
+            atan_unit(ay / ax)
+        } else {
+            Self::FRAC_PI_2 - atan_unit(ax / ay)
+        };
+        if x.is_negative() {
+            angle = Self::PI - angle;
+        }
+        if self.is_negative() {
+            -angle
+        } else {
+            angle
+        }
+    }
+}
+
+/// Arctangent for values in `[-1, 1]`
+fn atan_unit(t: Fixed) -> Fixed {
+    // Use atan(t) = 2 * atan(t / (1 + sqrt(1 + t^2))) to get argument below
+    // 0.42 and then sum Taylor series.
+    let r = t / (Fixed::ONE + (Fixed::ONE + t * t).sqrt());
+    let r2 = r * r;
+    let mut power = r;
+    let mut result = r;
+    for k in 1..10 {
+        power = -power * r2;
+        result += power / Fixed::from_int(2 * k + 1);
+    }
+    result * 2
+}
+
+/// Integer square root with Newton iterations
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = 1u128 << ((128 - value.leading_zeros()).div_ceil(2));
+    loop {
+        let y = (x + value / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+impl Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl From<i32> for Fixed {
+    fn from(value: i32) -> Self {
+        Fixed::from_int(value)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Fixed(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Fixed(self.0 - rhs.0)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Fixed(((self.0 as i128 * rhs.0 as i128) >> FRAC_BITS) as i64)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Fixed((((self.0 as i128) << FRAC_BITS) / rhs.0 as i128) as i64)
+    }
+}
+
+impl Rem for Fixed {
+    type Output = Fixed;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        Fixed(self.0 % rhs.0)
+    }
+}
+
+impl Mul<i32> for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        Fixed(self.0 * rhs as i64)
+    }
+}
+
+impl Div<i32> for Fixed {
+    type Output = Fixed;
+
+    fn div(self, rhs: i32) -> Self::Output {
+        Fixed(self.0 / rhs as i64)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Self::Output {
+        Fixed(-self.0)
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign for Fixed {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl RemAssign for Fixed {
+    fn rem_assign(&mut self, rhs: Self) {
+        *self = *self % rhs;
+    }
+}
+
+/// Two dimensional vector of fixed point numbers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FixedV2 {
+    pub x: Fixed,
+    pub y: Fixed,
+}
+
+impl FixedV2 {
+    pub const ZERO: FixedV2 = FixedV2 {
+        x: Fixed::ZERO,
+        y: Fixed::ZERO,
+    };
+
+    pub const fn new(x: Fixed, y: Fixed) -> Self {
+        FixedV2 { x, y }
+    }
+
+    pub const fn from_int(x: i32, y: i32) -> Self {
+        FixedV2::new(Fixed::from_int(x), Fixed::from_int(y))
+    }
+
+    pub fn from_f32(x: f32, y: f32) -> Self {
+        FixedV2::new(Fixed::from_f32(x), Fixed::from_f32(y))
+    }
+
+    pub fn to_f32(self) -> (f32, f32) {
+        (self.x.to_f32(), self.y.to_f32())
+    }
+
+    pub fn dot(self, other: Self) -> Fixed {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Z component of 3D cross product
+    pub fn cross(self, other: Self) -> Fixed {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn square_length(self) -> Fixed {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> Fixed {
+        self.square_length().sqrt()
+    }
+
+    pub fn square_distance(self, other: Self) -> Fixed {
+        (self - other).square_length()
+    }
+
+    pub fn distance(self, other: Self) -> Fixed {
+        (self - other).length()
+    }
+
+    /// Vector with the same direction and unit length. Zero vector stays zero.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length == Fixed::ZERO {
+            self
+        } else {
+            self / length
+        }
+    }
+
+    /// Rotate vector counter clockwise by angle in radians
+    pub fn rotate(self, angle: Fixed) -> Self {
+        let (sin, cos) = (angle.sin(), angle.cos());
+        FixedV2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// Angle of the vector relative to X axis
+    pub fn angle(self) -> Fixed {
+        self.y.atan2(self.x)
+    }
+}
+
+impl Add for FixedV2 {
+    type Output = FixedV2;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        FixedV2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for FixedV2 {
+    type Output = FixedV2;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        FixedV2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Neg for FixedV2 {
+    type Output = FixedV2;
+
+    fn neg(self) -> Self::Output {
+        FixedV2::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<Fixed> for FixedV2 {
+    type Output = FixedV2;
+
+    fn mul(self, rhs: Fixed) -> Self::Output {
+        FixedV2::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Mul<FixedV2> for Fixed {
+    type Output = FixedV2;
+
+    fn mul(self, rhs: FixedV2) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Div<Fixed> for FixedV2 {
+    type Output = FixedV2;
+
+    fn div(self, rhs: Fixed) -> Self::Output {
+        FixedV2::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl AddAssign for FixedV2 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for FixedV2 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign<Fixed> for FixedV2 {
+    fn mul_assign(&mut self, rhs: Fixed) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<Fixed> for FixedV2 {
+    fn div_assign(&mut self, rhs: Fixed) {
+        *self = *self / rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: Fixed, expected: f64, eps: f64) {
+        assert!(
+            (value.to_f64() - expected).abs() < eps,
+            "{value} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Fixed::from_int(3);
+        let b = Fixed::from_ratio(1, 2);
+        assert_eq!(a + b, Fixed::from_ratio(7, 2));
+        assert_eq!(a - b, Fixed::from_ratio(5, 2));
+        assert_eq!(a * b, Fixed::from_ratio(3, 2));
+        assert_eq!(a / b, Fixed::from_int(6));
+        assert_eq!(-a * -b, Fixed::from_ratio(3, 2));
+        assert_eq!(a % Fixed::from_int(2), Fixed::ONE);
+        assert_eq!(Fixed::from_ratio(-3, 2).floor(), Fixed::from_int(-2));
+        assert_eq!(Fixed::from_ratio(-3, 2).ceil(), Fixed::from_int(-1));
+        assert_eq!(Fixed::from_ratio(5, 2).round(), Fixed::from_int(3));
+        assert_eq!(Fixed::from_ratio(-3, 2).to_int(), -2);
+        assert_eq!(Fixed::from_ratio(-3, 2).fract(), Fixed::HALF);
+    }
+
+    #[test]
+    fn constants() {
+        assert_close(Fixed::PI, std::f64::consts::PI, 1e-9);
+        assert_close(Fixed::FRAC_PI_2, std::f64::consts::FRAC_PI_2, 1e-9);
+        assert_close(Fixed::TAU, std::f64::consts::TAU, 1e-9);
+    }
+
+    #[test]
+    fn sqrt() {
+        assert_eq!(Fixed::from_int(16).sqrt(), Fixed::from_int(4));
+        assert_eq!(Fixed::from_int(-16).sqrt(), Fixed::ZERO);
+        assert_close(Fixed::from_int(2).sqrt(), std::f64::consts::SQRT_2, 1e-9);
+        assert_close(Fixed::from_ratio(1, 100).sqrt(), 0.1, 1e-8);
+        assert_close(Fixed::from_int(1 << 30).sqrt(), 32768.0, 1e-9);
+    }
+
+    #[test]
+    fn trigonometry() {
+        for i in -100..100 {
+            let angle = Fixed::from_ratio(i, 10);
+            let expected = angle.to_f64();
+            assert_close(angle.sin(), expected.sin(), 1e-5);
+            assert_close(angle.cos(), expected.cos(), 1e-5);
+        }
+        for x in -10..10 {
+            for y in -10..10 {
+                let angle = Fixed::from_int(y).atan2(Fixed::from_int(x));
+                assert_close(angle, (y as f64).atan2(x as f64), 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn vectors() {
+        let v = FixedV2::from_int(3, 4);
+        assert_eq!(v.length(), Fixed::from_int(5));
+        assert_eq!(v.dot(FixedV2::from_int(1, 1)), Fixed::from_int(7));
+        assert_eq!(v * Fixed::from_int(2), FixedV2::from_int(6, 8));
+        assert_eq!(v - v, FixedV2::ZERO);
+        assert_close(v.normalize().length(), 1.0, 1e-6);
+        let rotated = FixedV2::from_int(1, 0).rotate(Fixed::FRAC_PI_2);
+        assert_close(rotated.x, 0.0, 1e-5);
+        assert_close(rotated.y, 1.0, 1e-5);
+    }
+
+    #[test]
+    fn serialization() {
+        let v = FixedV2::new(Fixed::from_ratio(-7, 3), Fixed::PI);
+        let mut buff = vec![];
+        ciborium::into_writer(&v, &mut buff).expect("encoded");
+        let decoded: FixedV2 = ciborium::from_reader(&buff[..]).expect("decoded");
+        assert_eq!(v, decoded);
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use crate::Turn;
+
+/// Queue of inputs that are scheduled to be applied at later turns.
+///
+/// Inputs that are produced at turn `t` are applied at turn `t + delay`. The
+/// delay hides the latency of network play and makes local play feel the same
+/// as online one. Inputs that arrive late (scheduled for turn that is already
+/// passed) are applied at the next taken turn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputQueue<I> {
+    delay: Turn,
+    pending: BTreeMap<Turn, Vec<I>>,
+}
+
+impl<I> Default for InputQueue<I> {
+    fn default() -> Self {
+        InputQueue::new(0)
+    }
+}
+
+impl<I> InputQueue<I> {
+    /// Create empty queue with given delay in turns
+    pub fn new(delay: Turn) -> Self {
+        InputQueue {
+            delay,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Amount of turns between producing of input and applying it
+    pub fn delay(&self) -> Turn {
+        self.delay
+    }
+
+    /// Schedule inputs that are produced at the `turn`. Returns turn at which
+    /// the inputs will be applied.
+    pub fn schedule<It: IntoIterator<Item = I>>(&mut self, turn: Turn, inputs: It) -> Turn {
+        let scheduled = turn + self.delay;
+        self.insert(scheduled, inputs);
+        scheduled
+    }
+
+    /// Put inputs exactly at the given turn without delay. Useful for inputs
+    /// that were already scheduled by remote side.
+    pub fn insert<It: IntoIterator<Item = I>>(&mut self, turn: Turn, inputs: It) {
+        let mut inputs = inputs.into_iter().peekable();
+        if inputs.peek().is_some() {
+            self.pending.entry(turn).or_default().extend(inputs);
+        }
+    }
+
+    /// Take all inputs that should be applied at the given turn including
+    /// late inputs from previous turns.
+    pub fn take(&mut self, turn: Turn) -> Vec<I> {
+        let later = self.pending.split_off(&(turn + 1));
+        let ready = std::mem::replace(&mut self.pending, later);
+        ready.into_values().flatten().collect()
+    }
+
+    /// Get inputs that are scheduled for the given turn
+    pub fn scheduled(&self, turn: Turn) -> &[I] {
+        self.pending.get(&turn).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// Drop all pending inputs
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Pending inputs by turns in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = (Turn, &[I])> {
+        self.pending
+            .iter()
+            .map(|(turn, inputs)| (*turn, inputs.as_slice()))
+    }
+
+    /// Check that there is no pending inputs
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_with_delay() {
+        let mut queue = InputQueue::new(2);
+        assert_eq!(queue.schedule(0, [1, 2]), 2);
+        assert_eq!(queue.schedule(1, [3]), 3);
+        assert_eq!(queue.take(0), Vec::<i32>::new());
+        assert_eq!(queue.take(1), Vec::<i32>::new());
+        assert_eq!(queue.take(2), vec![1, 2]);
+        assert_eq!(queue.scheduled(3), &[3]);
+        assert_eq!(queue.take(3), vec![3]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn late_inputs_are_applied() {
+        let mut queue = InputQueue::new(0);
+        queue.insert(1, [1]);
+        queue.insert(3, [3]);
+        queue.insert(2, []);
+        assert_eq!(queue.take(2), vec![1]);
+        assert_eq!(queue.take(4), vec![3]);
+        assert!(queue.is_empty());
+    }
+}
@@ -1,5 +1,16 @@
+pub mod fixed;
+pub mod input;
 pub mod replay;
+pub mod rng;
+pub mod tick;
 pub mod world;
 
+#[cfg(test)]
+pub(crate) mod test_util;
+
+pub use fixed::*;
+pub use input::*;
 pub use replay::*;
+pub use rng::*;
+pub use tick::*;
 pub use world::*;
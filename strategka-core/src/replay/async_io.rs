@@ -0,0 +1,141 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::Path;
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter},
+};
+
+use super::error::{Result, ResultOwned};
+use super::{encode_turn, log_incomplete, DecodeLimits, Replay, StreamInput};
+use crate::World;
+
+/// Amount of encoded bytes that are collected before writing to the sink
+const WRITE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Async IO for replays with the same format as the blocking one. Available
+/// with the `async` feature.
+impl<W: World + Default + Clone + Serialize + DeserializeOwned> Replay<W> {
+    /// Write down bytes of replay into the file located at given [path]
+    pub async fn save_async<P: AsRef<Path>>(&self, path: P) -> Result<'_, ()> {
+        let f = File::create(path).await?;
+        self.encode_async(f).await
+    }
+
+    /// Load replay from file with default [`DecodeLimits`]
+    pub async fn load_async<P: AsRef<Path>>(path: P) -> ResultOwned<Self> {
+        Self::load_async_with_limits(path, DecodeLimits::default()).await
+    }
+
+    /// Load replay from file rejecting replays that exceed the limits
+    pub async fn load_async_with_limits<P: AsRef<Path>>(
+        path: P,
+        limits: DecodeLimits,
+    ) -> ResultOwned<Self> {
+        let f = File::open(path.as_ref()).await?;
+        Self::decode_async_with_limits(f, limits)
+            .await
+            .map_err(|e| log_incomplete(e, path.as_ref()))
+    }
+
+    /// Stream serialized bytes of replay into the sink. Turns are encoded in
+    /// batches, so the whole replay is never kept in memory twice.
+    pub async fn encode_async<S: AsyncWrite + Unpin>(&self, sink: S) -> Result<'_, ()> {
+        let mut sink = BufWriter::new(sink);
+        let mut buff = vec![];
+        self.encode_head(&mut buff)?;
+        for turn in self.inputs.iter() {
+            encode_turn(turn, &mut buff)?;
+            if buff.len() >= WRITE_BUFFER_SIZE {
+                sink.write_all(&buff).await?;
+                buff.clear();
+            }
+        }
+        sink.write_all(&buff).await?;
+        sink.flush().await?;
+        Ok(())
+    }
+
+    /// Read replay from the stream with default [`DecodeLimits`]
+    pub async fn decode_async<R: AsyncRead + Unpin>(reader: R) -> ResultOwned<Self> {
+        Self::decode_async_with_limits(reader, DecodeLimits::default()).await
+    }
+
+    /// Read replay from the stream rejecting replays that exceed the limits.
+    /// The stream is read until the replay is parsed.
+    pub async fn decode_async_with_limits<R: AsyncRead + Unpin>(
+        mut reader: R,
+        limits: DecodeLimits,
+    ) -> ResultOwned<Self> {
+        let mut input = StreamInput::default();
+        loop {
+            input.buff.reserve(input.chunk_size());
+            let n = reader.read_buf(&mut input.buff).await?;
+            if let Some(result) = input.parse(&|input| Self::parser(input, limits), n == 0) {
+                return result;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::error::GenericError;
+    use crate::test_util::{TestInput, TestWorld};
+
+    fn make_replay(turns: u64) -> Replay<TestWorld> {
+        let mut replay = Replay::new(&TestWorld::new(42), 60);
+        for turn in 0..turns {
+            replay
+                .record(
+                    turn * 2,
+                    &[TestInput::Add(turn as u32), TestInput::Say("hi".into())],
+                )
+                .expect("record");
+        }
+        replay
+    }
+
+    #[tokio::test]
+    async fn encode_same_as_blocking() {
+        let replay = make_replay(10_000);
+        let mut blocking = vec![];
+        replay.encode(&mut blocking).expect("encoded");
+        let mut streamed = vec![];
+        replay.encode_async(&mut streamed).await.expect("encoded");
+        assert_eq!(blocking, streamed);
+
+        // Small pipe makes the parser retry on incomplete input
+        let replay = make_replay(100);
+        let (writer, reader) = tokio::io::duplex(7);
+        let (encoded, decoded) = tokio::join!(
+            async {
+                let result = replay.encode_async(writer).await;
+                result.map_err(|e| e.to_string())
+            },
+            Replay::decode_async(reader)
+        );
+        encoded.expect("encoded");
+        assert_eq!(replay, decoded.expect("decoded"));
+    }
+
+    #[tokio::test]
+    async fn save_load_test() {
+        let replay = make_replay(3);
+        let t = temp_file::TempFile::new().expect("temp file");
+        replay.save_async(t.path()).await.expect("save replay");
+        let loaded = Replay::<TestWorld>::load_async(t.path())
+            .await
+            .expect("load replay");
+        assert_eq!(replay, loaded);
+        assert_eq!(Replay::<TestWorld>::load(t.path()).expect("load"), replay);
+    }
+
+    #[tokio::test]
+    async fn incomplete_stream() {
+        let mut buffer = vec![];
+        make_replay(3).encode(&mut buffer).expect("encoded");
+        let result = Replay::<TestWorld>::decode_async(&buffer[..buffer.len() - 1]).await;
+        assert!(matches!(result, Err(GenericError::Incomplete(_))));
+    }
+}
@@ -8,18 +8,25 @@ use super::error::Error;
 
 pub type Parser<'a, T> = IResult<&'a [u8], T, Error<'a>>;
 
-pub fn length_decoding<'a, R, F>(f: F) -> impl FnMut(&'a [u8]) -> Parser<'a, Option<R>>
+/// Parse block prefixed with its length. Blocks longer than `max_len` are rejected
+/// before the body is awaited.
+pub fn length_decoding<'a, R, F>(
+    max_len: u64,
+    f: F,
+) -> impl FnMut(&'a [u8]) -> Parser<'a, Option<R>>
 where
     F: FnMut(&'a [u8]) -> Parser<'a, R> + Copy,
 {
     move |input| {
         let (input, len) = context("block length", be_u64)(input)?;
-        if input.len() < len as usize {
-            if let Some(nz_len) = NonZeroUsize::new(len as usize) {
-                return Err(Err::Incomplete(nom::Needed::Size(nz_len)));
-            } else {
-                return Err(Err::Error(Error::InvalidLength(len as usize, input.len())));
-            }
+        if len > max_len {
+            return Err(Err::Failure(Error::BlockTooLarge(len, max_len)));
+        }
+        if (input.len() as u64) < len {
+            let missing = NonZeroUsize::new((len - input.len() as u64) as usize);
+            return Err(Err::Incomplete(
+                missing.map_or(nom::Needed::Unknown, nom::Needed::Size),
+            ));
         }
         let restricted_input = &input[0..len as usize];
         let result = if len == 0 {
@@ -33,13 +40,22 @@ where
     }
 }
 
-pub fn decode_vec<'a, R, F>(item_parser: F) -> impl FnMut(&'a [u8]) -> Parser<'a, Vec<R>>
+/// Parse vector prefixed with amount of items. Vectors longer than `max_len`
+/// are rejected and preallocation never exceeds size of the input.
+pub fn decode_vec<'a, R, F>(
+    max_len: u64,
+    item_parser: F,
+) -> impl FnMut(&'a [u8]) -> Parser<'a, Vec<R>>
 where
     F: FnMut(&'a [u8]) -> Parser<'a, R> + Copy,
 {
     move |input| {
         let (input, len) = context("vector length", be_u64)(input)?;
-        let mut result = Vec::with_capacity(len as usize);
+        if len > max_len {
+            return Err(Err::Failure(Error::TooManyItems(len, max_len)));
+        }
+        // Each item takes at least one byte, so the input bounds real length
+        let mut result = Vec::with_capacity(len.min(input.len() as u64) as usize);
         let mut cycle_input = input;
         for _ in 0..len {
             let (input, item) = context("vector item", item_parser)(cycle_input)?;
@@ -0,0 +1,271 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::{self, Debug, Display};
+
+use super::{Playback, Replay};
+use crate::{Tick, Turn, World};
+
+/// Difference of inputs recorded at the same turn in two replays
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnDiff<I> {
+    pub turn: Turn,
+    /// Inputs that exist only in the second replay
+    pub added: Vec<I>,
+    /// Inputs that exist only in the first replay
+    pub removed: Vec<I>,
+    /// Inputs at the same position of the turn that differ, first replay goes first
+    pub changed: Vec<(I, I)>,
+}
+
+/// Structural difference between two replays
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayDiff<I> {
+    /// Simulation rates if they differ
+    pub rate: Option<(u32, u32)>,
+    /// Amounts of turns if they differ
+    pub total_turns: Option<(Turn, Turn)>,
+    /// Whether initial states of simulation differ
+    pub initial: bool,
+    /// Turns with different inputs in ascending order
+    pub turns: Vec<TurnDiff<I>>,
+}
+
+impl<I> ReplayDiff<I> {
+    /// Check that replays are the same
+    pub fn is_empty(&self) -> bool {
+        self.rate.is_none() && self.total_turns.is_none() && !self.initial && self.turns.is_empty()
+    }
+
+    /// First turn which inputs differ
+    pub fn first_turn(&self) -> Option<Turn> {
+        self.turns.first().map(|t| t.turn)
+    }
+}
+
+impl<I: Debug> Display for ReplayDiff<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "Replays are equal");
+        }
+        if let Some((r1, r2)) = self.rate {
+            writeln!(f, "rate: {r1} -> {r2}")?;
+        }
+        if let Some((t1, t2)) = self.total_turns {
+            writeln!(f, "total turns: {t1} -> {t2}")?;
+        }
+        if self.initial {
+            writeln!(f, "initial state differs")?;
+        }
+        if let Some(turn) = self.first_turn() {
+            writeln!(f, "first differing turn: {turn}")?;
+        }
+        for turn in self.turns.iter() {
+            writeln!(f, "turn {}:", turn.turn)?;
+            for (old, new) in turn.changed.iter() {
+                writeln!(f, "  ~ {old:?} -> {new:?}")?;
+            }
+            for input in turn.removed.iter() {
+                writeln!(f, "  - {input:?}")?;
+            }
+            for input in turn.added.iter() {
+                writeln!(f, "  + {input:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare inputs of two replays turn by turn
+pub fn diff_inputs<I: PartialEq + Clone>(
+    first: &[(Turn, Vec<I>)],
+    second: &[(Turn, Vec<I>)],
+) -> Vec<TurnDiff<I>> {
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < first.len() || j < second.len() {
+        let turn1 = first.get(i).map(|(t, _)| *t).unwrap_or(Turn::MAX);
+        let turn2 = second.get(j).map(|(t, _)| *t).unwrap_or(Turn::MAX);
+        let (turn, inputs1, inputs2): (_, &[I], &[I]) = if turn1 == turn2 {
+            i += 1;
+            j += 1;
+            (turn1, &first[i - 1].1, &second[j - 1].1)
+        } else if turn1 < turn2 {
+            i += 1;
+            (turn1, &first[i - 1].1, &[])
+        } else {
+            j += 1;
+            (turn2, &[], &second[j - 1].1)
+        };
+        let common = inputs1.len().min(inputs2.len());
+        let diff = TurnDiff {
+            turn,
+            added: inputs2[common..].to_vec(),
+            removed: inputs1[common..].to_vec(),
+            changed: inputs1[..common]
+                .iter()
+                .zip(inputs2[..common].iter())
+                .filter(|(a, b)| a != b)
+                .map(|(a, b)| (a.clone(), b.clone()))
+                .collect(),
+        };
+        if !diff.added.is_empty() || !diff.removed.is_empty() || !diff.changed.is_empty() {
+            result.push(diff);
+        }
+    }
+    result
+}
+
+/// Collect difference of replays from their rates, amounts of turns, equality of
+/// initial states and inputs, first replay goes first in the pairs
+pub(super) fn diff_replays<I: PartialEq + Clone>(
+    (rate1, rate2): (u32, u32),
+    (total1, total2): (Turn, Turn),
+    initial_equal: bool,
+    first: &[(Turn, Vec<I>)],
+    second: &[(Turn, Vec<I>)],
+) -> ReplayDiff<I> {
+    ReplayDiff {
+        rate: (rate1 != rate2).then_some((rate1, rate2)),
+        total_turns: (total1 != total2).then_some((total1, total2)),
+        initial: !initial_equal,
+        turns: diff_inputs(first, second),
+    }
+}
+
+impl<W: World + Default + Clone + PartialEq + Serialize + DeserializeOwned> Replay<W> {
+    /// Find structural difference between the replay and the other one
+    pub fn diff(&self, other: &Replay<W>) -> ReplayDiff<W::Input> {
+        diff_replays(
+            (self.rate, other.rate),
+            (self.total_turns, other.total_turns),
+            self.initial == other.initial,
+            &self.inputs,
+            &other.inputs,
+        )
+    }
+
+    /// Simulate both replays side by side and find first turn after which
+    /// states of simulations differ. Returns `None` if the states are equal
+    /// during the whole replays.
+    pub fn diverging_turn<I, S, E>(
+        &self,
+        other: &Replay<W>,
+        mut input_handler: I,
+        mut simulate: S,
+    ) -> Result<Option<Turn>, E>
+    where
+        I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+        S: FnMut(&mut W, Tick) -> Result<(), E>,
+    {
+        let mut first = Playback::new(self);
+        let mut second = Playback::new(other);
+        if first.state() != second.state() {
+            return Ok(Some(0));
+        }
+        for turn in 0..=self.total_turns.max(other.total_turns) {
+            first.step(&mut input_handler, &mut simulate)?;
+            second.step(&mut input_handler, &mut simulate)?;
+            if first.state() != second.state() {
+                return Ok(Some(turn));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{input_handler, simulate, CounterInput, CounterWorld};
+
+    #[test]
+    fn diff_test() {
+        let mut replay1 = Replay::new(&CounterWorld::default(), 60);
+        replay1.record(1, &[CounterInput::Add(1)]).expect("record");
+        replay1
+            .record(3, &[CounterInput::Add(2), CounterInput::Add(3)])
+            .expect("record");
+        replay1.record(5, &[CounterInput::Add(4)]).expect("record");
+        assert!(replay1.diff(&replay1).is_empty());
+
+        let mut replay2 = Replay::new(&CounterWorld::default(), 60);
+        replay2.record(1, &[CounterInput::Add(1)]).expect("record");
+        replay2
+            .record(3, &[CounterInput::Log("hi".to_owned())])
+            .expect("record");
+        replay2.record(4, &[CounterInput::Add(4)]).expect("record");
+        replay2
+            .record(6, &[CounterInput::Log("end".to_owned())])
+            .expect("record");
+
+        let diff = replay1.diff(&replay2);
+        assert_eq!(diff.rate, None);
+        assert_eq!(diff.total_turns, Some((5, 6)));
+        assert!(!diff.initial);
+        assert_eq!(diff.first_turn(), Some(3));
+        assert_eq!(
+            diff.turns,
+            vec![
+                TurnDiff {
+                    turn: 3,
+                    added: vec![],
+                    removed: vec![CounterInput::Add(3)],
+                    changed: vec![(CounterInput::Add(2), CounterInput::Log("hi".to_owned()))],
+                },
+                TurnDiff {
+                    turn: 4,
+                    added: vec![CounterInput::Add(4)],
+                    removed: vec![],
+                    changed: vec![],
+                },
+                TurnDiff {
+                    turn: 5,
+                    added: vec![],
+                    removed: vec![CounterInput::Add(4)],
+                    changed: vec![],
+                },
+                TurnDiff {
+                    turn: 6,
+                    added: vec![CounterInput::Log("end".to_owned())],
+                    removed: vec![],
+                    changed: vec![],
+                },
+            ]
+        );
+        assert!(diff.to_string().contains("first differing turn: 3"));
+    }
+
+    #[test]
+    fn diverging_turn_test() {
+        let mut replay1 = Replay::new(&CounterWorld::default(), 60);
+        replay1.record(1, &[CounterInput::Add(1)]).expect("record");
+        replay1.record(3, &[CounterInput::Add(2)]).expect("record");
+
+        let mut replay2 = replay1.clone();
+        replay2.inputs[0]
+            .1
+            .push(CounterInput::Log("no effect".to_owned()));
+        assert_eq!(
+            replay1.diverging_turn(&replay2, input_handler, simulate),
+            Ok(None)
+        );
+
+        replay2.record(4, &[CounterInput::Add(0)]).expect("record");
+        replay2.record(5, &[CounterInput::Add(1)]).expect("record");
+        // Turns past the end of a replay are not simulated, equal length isolates the inputs
+        replay1.total_turns = replay2.total_turns;
+        assert_eq!(
+            replay1.diverging_turn(&replay2, input_handler, simulate),
+            Ok(Some(5))
+        );
+
+        let world = CounterWorld {
+            value: 1,
+            ..Default::default()
+        };
+        let replay3 = Replay::new(&world, 60);
+        assert_eq!(
+            replay1.diverging_turn(&replay3, input_handler, simulate),
+            Ok(Some(0))
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::ops::{Bound, RangeBounds};
+
+use super::error::{Error, Result};
+use super::{Playback, Replay};
+use crate::{Tick, Turn, World};
+
+/// Editing operations that keep invariants of [`Replay::record`]: turns of
+/// inputs are strictly increasing and `total_turns` covers the last input.
+impl<W: World + Default + Clone + Serialize + DeserializeOwned> Replay<W> {
+    /// Run simulation from the initial state till the given turn. Inputs of
+    /// the turns before the given one are applied and the turns are simulated.
+    /// Simulation stops earlier the same as [`Playback`] does: if the last
+    /// input of a turn returns `true` or at `total_turns`.
+    pub fn simulate_to<I, S, E>(
+        &self,
+        turn: Turn,
+        input_handler: I,
+        simulate: S,
+    ) -> std::result::Result<W, E>
+    where
+        I: FnMut(&mut W, &W::Input) -> std::result::Result<bool, E>,
+        S: FnMut(&mut W, Tick) -> std::result::Result<(), E>,
+    {
+        let mut playback = Playback::new(self);
+        playback.advance_to(turn, input_handler, simulate)?;
+        Ok(playback.into_state())
+    }
+
+    /// Keep only turns in range `start..end`. The initial state is replaced
+    /// with state of simulation at `start` and turns are counted from it.
+    pub fn trim<I, S, E>(
+        &mut self,
+        start: Turn,
+        end: Turn,
+        input_handler: I,
+        simulate: S,
+    ) -> std::result::Result<(), E>
+    where
+        I: FnMut(&mut W, &W::Input) -> std::result::Result<bool, E>,
+        S: FnMut(&mut W, Tick) -> std::result::Result<(), E>,
+    {
+        let start = start.min(self.total_turns);
+        self.initial = self.simulate_to(start, input_handler, simulate)?;
+        self.truncate(end.max(start));
+        self.inputs.retain(|(t, _)| *t >= start);
+        for (t, _) in self.inputs.iter_mut() {
+            *t -= start;
+        }
+        self.total_turns -= start;
+        Ok(())
+    }
+
+    /// Append inputs of other replay after the end of the current one. The
+    /// other replay is expected to start from the final state of the current
+    /// one, so its initial state is ignored.
+    pub fn splice(&mut self, other: &Replay<W>) -> Result<'static, ()> {
+        if self.rate != other.rate {
+            return Err(Error::IncompatibleRate(self.rate, other.rate));
+        }
+        let offset = self.total_turns;
+        for (turn, inputs) in other.inputs.iter() {
+            match self.inputs.last_mut() {
+                Some((last_turn, last_inputs)) if *last_turn == turn + offset => {
+                    last_inputs.extend_from_slice(inputs);
+                }
+                _ => self.inputs.push((turn + offset, inputs.clone())),
+            }
+        }
+        self.total_turns += other.total_turns;
+        Ok(())
+    }
+
+    /// Rewrite each recorded input. Returning `None` drops the input, turns
+    /// without inputs left are removed.
+    pub fn map_inputs<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Turn, W::Input) -> Option<W::Input>,
+    {
+        let inputs = std::mem::take(&mut self.inputs);
+        self.inputs = inputs
+            .into_iter()
+            .filter_map(|(turn, turn_inputs)| {
+                let mapped: Vec<_> = turn_inputs
+                    .into_iter()
+                    .filter_map(|input| f(turn, input))
+                    .collect();
+                (!mapped.is_empty()).then_some((turn, mapped))
+            })
+            .collect();
+    }
+
+    /// Cut the turns out of the replay. Inputs of the turns are dropped and
+    /// later turns are shifted back.
+    pub fn remove_turns<R: RangeBounds<Turn>>(&mut self, range: R) {
+        let start = match range.start_bound() {
+            Bound::Included(t) => *t,
+            Bound::Excluded(t) => t.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(t) => t.saturating_add(1),
+            Bound::Excluded(t) => *t,
+            Bound::Unbounded => Turn::MAX,
+        };
+        if start >= end {
+            return;
+        }
+        let len = end - start;
+        self.inputs.retain(|(t, _)| *t < start || *t >= end);
+        for (t, _) in self.inputs.iter_mut() {
+            if *t >= end {
+                *t -= len;
+            }
+        }
+        self.total_turns -= self.total_turns.min(end) - self.total_turns.min(start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{input_handler, simulate, CounterInput, CounterWorld};
+
+    fn make_replay() -> Replay<CounterWorld> {
+        let mut replay = Replay::new(&CounterWorld::default(), 60);
+        replay.record(1, &[CounterInput::Add(1)]).expect("record");
+        replay.record(3, &[CounterInput::Add(2)]).expect("record");
+        replay.record(6, &[CounterInput::Add(4)]).expect("record");
+        replay.total_turns = 8;
+        replay
+    }
+
+    #[test]
+    fn simulate_to_test() {
+        let replay = make_replay();
+        let state = replay.simulate_to(4, input_handler, simulate).unwrap();
+        assert_eq!(state, CounterWorld { value: 3, ticks: 4 });
+
+        let mut ended = make_replay();
+        ended.inputs[1].1.push(CounterInput::End);
+        let state = ended.simulate_to(8, input_handler, simulate).unwrap();
+        assert_eq!(state, CounterWorld { value: 3, ticks: 3 });
+    }
+
+    #[test]
+    fn trim_test() {
+        let mut replay = make_replay();
+        replay.trim(3, 7, input_handler, simulate).unwrap();
+        assert_eq!(replay.initial, CounterWorld { value: 1, ticks: 3 });
+        assert_eq!(
+            replay.inputs,
+            vec![
+                (0, vec![CounterInput::Add(2)]),
+                (3, vec![CounterInput::Add(4)])
+            ]
+        );
+        assert_eq!(replay.total_turns, 4);
+
+        let full = make_replay();
+        let final_state = full.simulate_to(7, input_handler, simulate).unwrap();
+        let trimmed_state = replay.simulate_to(4, input_handler, simulate).unwrap();
+        assert_eq!(final_state, trimmed_state);
+
+        let mut replay = make_replay();
+        replay.trim(10, 20, input_handler, simulate).unwrap();
+        assert_eq!(replay.total_turns, 0);
+        assert!(replay.inputs.is_empty());
+    }
+
+    #[test]
+    fn splice_test() {
+        let mut replay = make_replay();
+        let mut other = Replay::new(&CounterWorld::default(), 60);
+        other.record(0, &[CounterInput::Add(8)]).expect("record");
+        other.record(2, &[CounterInput::Add(16)]).expect("record");
+        replay.splice(&other).expect("splice");
+        assert_eq!(replay.total_turns, 10);
+        assert_eq!(
+            replay.inputs[3..],
+            [
+                (8, vec![CounterInput::Add(8)]),
+                (10, vec![CounterInput::Add(16)])
+            ]
+        );
+
+        let mut replay = make_replay();
+        replay.total_turns = 6;
+        replay.splice(&other).expect("splice");
+        assert_eq!(
+            replay.inputs[2],
+            (6, vec![CounterInput::Add(4), CounterInput::Add(8)])
+        );
+        replay.record(9, &[]).expect("record after splice");
+
+        // The error doesn't borrow the replay, so it can be edited further
+        let other = Replay::new(&CounterWorld::default(), 30);
+        let mut replay = make_replay();
+        let err = replay.splice(&other).expect_err("different rates");
+        replay.record(9, &[]).expect("record after failed splice");
+        assert!(matches!(err, Error::IncompatibleRate(60, 30)));
+    }
+
+    #[test]
+    fn map_inputs_test() {
+        let mut replay = make_replay();
+        replay.map_inputs(|turn, input| match input {
+            CounterInput::Add(2) => None,
+            CounterInput::Add(v) => Some(CounterInput::Add(v + turn as i64)),
+            other => Some(other),
+        });
+        assert_eq!(
+            replay.inputs,
+            vec![
+                (1, vec![CounterInput::Add(2)]),
+                (6, vec![CounterInput::Add(10)])
+            ]
+        );
+        assert_eq!(replay.total_turns, 8);
+    }
+
+    #[test]
+    fn remove_turns_test() {
+        let mut replay = make_replay();
+        replay.remove_turns(2..4);
+        assert_eq!(
+            replay.inputs,
+            vec![
+                (1, vec![CounterInput::Add(1)]),
+                (4, vec![CounterInput::Add(4)])
+            ]
+        );
+        assert_eq!(replay.total_turns, 6);
+
+        let mut replay = make_replay();
+        replay.remove_turns(5..);
+        assert_eq!(replay.inputs.len(), 2);
+        assert_eq!(replay.total_turns, 5);
+
+        let mut replay = make_replay();
+        replay.remove_turns(..=1);
+        assert_eq!(replay.inputs[0], (1, vec![CounterInput::Add(2)]));
+        assert_eq!(replay.total_turns, 6);
+    }
+}
@@ -35,6 +35,24 @@ pub enum GenericError<I: Debug> {
     Context(&'static str, Box<Self>),
     #[error("Parsing failed as incomplete input provided. Needed {0:?}")]
     Incomplete(Needed),
+    #[error("Simulation rate must be positive")]
+    ZeroRate,
+    #[error("Replays have different simulation rates: {0} and {1}")]
+    IncompatibleRate(u32, u32),
+    #[error("Replay has {0} turns, but the limit is {1}")]
+    TooManyTurns(u64, u64),
+    #[error("Vector has {0} items, but the limit is {1}")]
+    TooManyItems(u64, u64),
+    #[error("Length prefixed block has {0} bytes, but the limit is {1}")]
+    BlockTooLarge(u64, u64),
+    #[error("Failed to convert cbor value: {0}")]
+    Value(#[from] ciborium::value::Error),
+    #[cfg(feature = "text")]
+    #[error("Failed to encode text replay: {0}")]
+    TextEncoder(#[from] ron::Error),
+    #[cfg(feature = "text")]
+    #[error("Failed to decode text replay: {0}")]
+    TextDecoder(#[from] ron::error::SpannedError),
 }
 
 /// Error that shares part of original buffer
@@ -66,6 +84,16 @@ impl<'a> GenericError<&'a [u8]> {
                 GenericError::Context(v, Box::new(other.into_owned()))
             }
             GenericError::Incomplete(needed) => GenericError::Incomplete(needed),
+            GenericError::ZeroRate => GenericError::ZeroRate,
+            GenericError::IncompatibleRate(r1, r2) => GenericError::IncompatibleRate(r1, r2),
+            GenericError::TooManyTurns(n, m) => GenericError::TooManyTurns(n, m),
+            GenericError::TooManyItems(n, m) => GenericError::TooManyItems(n, m),
+            GenericError::BlockTooLarge(n, m) => GenericError::BlockTooLarge(n, m),
+            GenericError::Value(e) => GenericError::Value(e),
+            #[cfg(feature = "text")]
+            GenericError::TextEncoder(e) => GenericError::TextEncoder(e),
+            #[cfg(feature = "text")]
+            GenericError::TextDecoder(e) => GenericError::TextDecoder(e),
         }
     }
 }
@@ -0,0 +1,280 @@
+use ciborium::Value;
+use nom::{error::context, number::streaming::be_u64};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs::File, io::Write, path::Path};
+
+use super::diff::{diff_replays, ReplayDiff};
+use super::error::{Error, Result, ResultOwned};
+use super::{
+    ciborium_into_writer, ciborium_parse, encode_be_u32, encode_be_u64, encode_generic_header,
+    encode_turn, finish_parsing, length_decoding, length_encoded, load_file, magic_to_text,
+    parse_generic_header, parse_inputs, parse_rate, DecodeLimits, Parser, Replay,
+};
+use crate::{Turn, World};
+
+/// Replay of any game decoded without the game types. The initial state and
+/// inputs are kept as CBOR values, so tools can inspect replays without
+/// linking the game code.
+///
+/// Only magic bytes and version of the replay format are validated, game
+/// magic bytes and version are stored as is.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GenericReplay {
+    /// Magic bytes of the game that recorded the replay
+    pub game_magic: [u8; 4],
+    /// Version of the game that recorded the replay
+    pub game_version: u32,
+    /// Simulation turns per second
+    pub rate: u32,
+    /// Initial state of simulation, `None` if the replay stores empty block
+    /// for the default state
+    pub initial: Option<Value>,
+    /// Amount of turns until the simulation should run
+    pub total_turns: u64,
+    /// All recorded inputs
+    pub inputs: Vec<(Turn, Vec<Value>)>,
+}
+
+impl GenericReplay {
+    /// Load replay of any game from file
+    pub fn load<P: AsRef<Path> + Clone>(path: P) -> ResultOwned<Self> {
+        Self::load_with_limits(path, DecodeLimits::default())
+    }
+
+    /// Load replay of any game from file rejecting replays that exceed the limits
+    pub fn load_with_limits<P: AsRef<Path> + Clone>(
+        path: P,
+        limits: DecodeLimits,
+    ) -> ResultOwned<Self> {
+        load_file(path, |input| Self::parser(input, limits))
+    }
+
+    /// Decode replay of any game with default [`DecodeLimits`]
+    pub fn decode(bytes: &[u8]) -> Result<'_, Self> {
+        Self::decode_with_limits(bytes, DecodeLimits::default())
+    }
+
+    /// Decode replay of any game rejecting replays that exceed the limits
+    pub fn decode_with_limits(bytes: &[u8], limits: DecodeLimits) -> Result<'_, Self> {
+        finish_parsing(Self::parser(bytes, limits))
+    }
+
+    /// Write down bytes of replay into the file located at given [path]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<'_, ()> {
+        let f = File::create(path)?;
+        self.encode(f)?;
+        Ok(())
+    }
+
+    /// Write down serialized bytes of replay in the same format as [`Replay::encode`]
+    pub fn encode<S: Write>(&self, mut sink: S) -> Result<'_, ()> {
+        encode_generic_header(self.game_magic, self.game_version, &mut sink)?;
+        encode_be_u32(self.rate, &mut sink)?;
+        length_encoded(&mut sink, |sink| match &self.initial {
+            Some(initial) => ciborium_into_writer(initial, sink),
+            None => Ok(()),
+        })?;
+        encode_be_u64(self.total_turns, &mut sink)?;
+        encode_be_u64(self.inputs.len() as u64, &mut sink)?;
+        for turn in self.inputs.iter() {
+            encode_turn(turn, &mut sink)?;
+        }
+        Ok(())
+    }
+
+    /// Game magic bytes as text if they are printable and in hex otherwise
+    pub fn game_name(&self) -> String {
+        magic_to_text(self.game_magic)
+    }
+
+    /// Check whether the replay was recorded by the game
+    pub fn is_game<W: World>(&self) -> bool {
+        self.game_magic == W::magic_bytes() && W::guard_version(self.game_version)
+    }
+
+    /// Convert typed replay into generic one
+    pub fn from_replay<W>(replay: &Replay<W>) -> Result<'static, Self>
+    where
+        W: World + Default + Clone + Serialize + DeserializeOwned,
+    {
+        let inputs = replay
+            .inputs
+            .iter()
+            .map(|(turn, inputs)| {
+                let values = inputs
+                    .iter()
+                    .map(Value::serialized)
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok((*turn, values))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(GenericReplay {
+            game_magic: W::magic_bytes(),
+            game_version: W::current_version(),
+            rate: replay.rate,
+            initial: Some(Value::serialized(&replay.initial)?),
+            total_turns: replay.total_turns,
+            inputs,
+        })
+    }
+
+    /// Convert into typed replay of the game. Game magic bytes and version are
+    /// checked the same way as [`Replay::decode`] does.
+    pub fn to_replay<W>(&self) -> Result<'static, Replay<W>>
+    where
+        W: World + Default + Clone + Serialize + DeserializeOwned,
+    {
+        if self.game_magic != W::magic_bytes() {
+            return Err(Error::InvalidMagic(self.game_magic));
+        }
+        if !W::guard_version(self.game_version) {
+            return Err(Error::UnsupportedGameVersion(self.game_version));
+        }
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|(turn, values)| {
+                let inputs = values
+                    .iter()
+                    .map(|value| value.deserialized())
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok((*turn, inputs))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Replay {
+            rate: self.rate,
+            initial: match &self.initial {
+                Some(initial) => initial.deserialized()?,
+                None => W::default(),
+            },
+            total_turns: self.total_turns,
+            inputs,
+        })
+    }
+
+    /// Find structural difference between the replay and the other one
+    pub fn diff(&self, other: &GenericReplay) -> ReplayDiff<Value> {
+        diff_replays(
+            (self.rate, other.rate),
+            (self.total_turns, other.total_turns),
+            self.initial == other.initial,
+            &self.inputs,
+            &other.inputs,
+        )
+    }
+
+    fn parser(input: &[u8], limits: DecodeLimits) -> Parser<'_, Self> {
+        let (input, (game_magic, game_version)) = parse_generic_header(input)?;
+        let (input, rate) = context("simulation rate", parse_rate)(input)?;
+        let (input, initial) = context(
+            "initial world",
+            length_decoding(limits.max_initial_size, ciborium_parse),
+        )(input)?;
+        let (input, total_turns) = context("total_turns", be_u64)(input)?;
+        let (input, inputs) = parse_inputs(input, total_turns, limits)?;
+        Ok((
+            input,
+            GenericReplay {
+                game_magic,
+                game_version,
+                rate,
+                initial,
+                total_turns,
+                inputs,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::error::GenericError;
+    use crate::test_util::{TestInput, TestWorld};
+
+    fn make_replay() -> Replay<TestWorld> {
+        let world = TestWorld {
+            field1: 42,
+            names: vec!["a".into()],
+        };
+        let mut replay = Replay::new(&world, 60);
+        replay.record(1, &[TestInput::Add(4)]).expect("record");
+        replay
+            .record(4, &[TestInput::Say("hi".into()), TestInput::Add(2)])
+            .expect("record");
+        replay
+    }
+
+    #[test]
+    fn decode_without_game_types() {
+        let replay = make_replay();
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+
+        let generic = GenericReplay::decode(&buffer).expect("decoded");
+        assert_eq!(generic.game_name(), "TWDT");
+        assert_eq!(generic.game_version, 2);
+        assert!(generic.is_game::<TestWorld>());
+        assert_eq!(generic.total_turns, 4);
+        assert_eq!(
+            generic.inputs[1].1[0],
+            Value::Map(vec![(Value::Text("Say".into()), Value::Text("hi".into()))])
+        );
+        assert_eq!(
+            generic,
+            GenericReplay::from_replay(&replay).expect("generic")
+        );
+
+        let mut reencoded = vec![];
+        generic.encode(&mut reencoded).expect("encoded");
+        assert_eq!(buffer, reencoded);
+        assert_eq!(generic.to_replay::<TestWorld>().expect("typed"), replay);
+    }
+
+    #[test]
+    fn checks_only_core_header() {
+        let replay = make_replay();
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+        // Game magic bytes follow core magic and version
+        buffer[8..12].copy_from_slice(b"ZZZZ");
+        let generic = GenericReplay::decode(&buffer).expect("decoded");
+        assert_eq!(&generic.game_magic, b"ZZZZ");
+        assert!(!generic.is_game::<TestWorld>());
+        assert!(matches!(
+            generic.to_replay::<TestWorld>(),
+            Err(GenericError::InvalidMagic(m)) if &m == b"ZZZZ"
+        ));
+
+        buffer[0] = b'X';
+        assert!(GenericReplay::decode(&buffer).is_err());
+    }
+
+    #[test]
+    fn generic_diff() {
+        let first = GenericReplay::from_replay(&make_replay()).expect("generic");
+        let mut other = make_replay();
+        other.record(6, &[TestInput::Add(1)]).expect("record");
+        let second = GenericReplay::from_replay(&other).expect("generic");
+        let diff = first.diff(&second);
+        assert_eq!(diff.total_turns, Some((4, 6)));
+        assert_eq!(diff.first_turn(), Some(6));
+    }
+
+    #[test]
+    fn empty_initial_block() {
+        let mut generic = GenericReplay::from_replay(&make_replay()).expect("generic");
+        generic.initial = None;
+        let mut buffer = vec![];
+        generic.encode(&mut buffer).expect("encoded");
+
+        let decoded = GenericReplay::decode(&buffer).expect("decoded");
+        assert_eq!(decoded.initial, None);
+        let typed = decoded.to_replay::<TestWorld>().expect("typed");
+        assert_eq!(typed.initial, TestWorld::default());
+        assert_eq!(
+            Replay::<TestWorld>::decode(&buffer).expect("decoded"),
+            typed
+        );
+    }
+}
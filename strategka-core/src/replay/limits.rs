@@ -0,0 +1,38 @@
+/// Limits that are checked while decoding replays and saves. Length prefixes
+/// of untrusted files are compared against them before any allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum amount of turns in replay and turns with inputs
+    pub max_turns: u64,
+    /// Maximum amount of inputs recorded at single turn
+    pub max_inputs_per_turn: u64,
+    /// Maximum size of single encoded input in bytes
+    pub max_input_size: u64,
+    /// Maximum size of encoded initial or saved world in bytes
+    pub max_initial_size: u64,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            // More than two years of simulation at 60 turns per second
+            max_turns: 1 << 32,
+            max_inputs_per_turn: 1 << 16,
+            max_input_size: 1 << 20,
+            max_initial_size: 1 << 28,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Limits that accept anything the format can express. Capacity of
+    /// allocations is still bounded by the size of input.
+    pub fn unlimited() -> Self {
+        DecodeLimits {
+            max_turns: u64::MAX,
+            max_inputs_per_turn: u64::MAX,
+            max_input_size: u64::MAX,
+            max_initial_size: u64::MAX,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use memmap2::Mmap;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs::File, path::Path};
+
+use super::error::{Result, ResultOwned};
+use super::{finish_parsing, DecodeLimits, Replay, ReplayView};
+use crate::World;
+
+/// Replay file mapped into memory. Parsing works directly with the mapped
+/// bytes without copying them into a buffer. Available with the `mmap` feature.
+///
+/// The file must not be modified while it is mapped.
+pub struct MappedReplay {
+    map: Mmap,
+}
+
+impl MappedReplay {
+    /// Map file located at given [path]
+    pub fn open<P: AsRef<Path>>(path: P) -> ResultOwned<Self> {
+        let f = File::open(path)?;
+        // Safety: the mapping is read only, modification of the file by other
+        // processes is documented as unsupported.
+        let map = unsafe { Mmap::map(&f)? };
+        Ok(MappedReplay { map })
+    }
+
+    /// Bytes of the mapped file
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Borrowed view of the replay with lazily decoded inputs
+    pub fn view<W>(&self, limits: DecodeLimits) -> Result<'_, ReplayView<'_, W>>
+    where
+        W: World + Default + Clone + Serialize + DeserializeOwned,
+    {
+        ReplayView::decode_with_limits(&self.map, limits)
+    }
+
+    /// Decode the whole replay
+    pub fn replay<W>(&self, limits: DecodeLimits) -> Result<'_, Replay<W>>
+    where
+        W: World + Default + Clone + Serialize + DeserializeOwned,
+    {
+        finish_parsing(Replay::parser(&self.map, limits))
+    }
+}
+
+impl<W: World + Default + Clone + Serialize + DeserializeOwned> Replay<W> {
+    /// Load replay from memory mapped file with default [`DecodeLimits`]
+    pub fn load_mmap<P: AsRef<Path>>(path: P) -> ResultOwned<Self> {
+        Self::load_mmap_with_limits(path, DecodeLimits::default())
+    }
+
+    /// Load replay from memory mapped file rejecting replays that exceed the limits
+    pub fn load_mmap_with_limits<P: AsRef<Path>>(
+        path: P,
+        limits: DecodeLimits,
+    ) -> ResultOwned<Self> {
+        let mapped = MappedReplay::open(path)?;
+        let replay = mapped.replay(limits).map_err(|e| e.into_owned())?;
+        Ok(replay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{TestInput, TestWorld};
+
+    #[test]
+    fn mmap_load_test() {
+        let mut replay = Replay::new(&TestWorld::new(42), 60);
+        replay.record(1, &[TestInput::Add(4)]).expect("record");
+        replay.record(5, &[TestInput::Add(8)]).expect("record");
+        let t = temp_file::TempFile::new().expect("temp file");
+        replay.save(t.path()).expect("save replay");
+
+        assert_eq!(
+            Replay::<TestWorld>::load_mmap(t.path()).expect("load"),
+            replay
+        );
+        let mapped = MappedReplay::open(t.path()).expect("mapped");
+        let view = mapped
+            .view::<TestWorld>(DecodeLimits::default())
+            .expect("view");
+        assert_eq!(view.inputs(5).expect("inputs"), vec![TestInput::Add(8)]);
+    }
+}
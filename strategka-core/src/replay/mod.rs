@@ -1,6 +1,19 @@
+#[cfg(feature = "async")]
+mod async_io;
 mod decoder;
+pub mod diff;
+mod edit;
 mod encoder;
 pub mod error;
+mod generic;
+pub mod limits;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod playback;
+pub mod save;
+#[cfg(feature = "text")]
+mod text;
+mod view;
 
 use nom::{
     bytes::streaming::take,
@@ -13,10 +26,20 @@ use std::io::Read;
 use std::{fs::File, io::Write, path::Path};
 
 use crate::World;
-use error::{Error, GenericError, Result, ResultOwned};
+use error::{Error, ErrorOwned, GenericError, Result, ResultOwned};
 
 use self::decoder::*;
+pub use self::diff::{ReplayDiff, TurnDiff};
 use self::encoder::*;
+pub use self::generic::GenericReplay;
+pub use self::limits::DecodeLimits;
+#[cfg(feature = "mmap")]
+pub use self::mmap::MappedReplay;
+pub use self::playback::Playback;
+pub use self::save::SaveGame;
+#[cfg(feature = "text")]
+pub use self::text::TextReplay;
+pub use self::view::ReplayView;
 
 /// Each tick simulation has a number from the begining
 pub type Turn = u64;
@@ -53,6 +76,10 @@ impl<W: World + Default> Default for Replay<W> {
 const MAGIC_BYTES: [u8; 4] = [0x53, 0x54, 0x47, 0x52];
 // Current maximum format version of replays the code supports
 const REPLAY_FORMAT_VERSION: u32 = 1;
+// Maximum size of blocks the files are read with
+const CHUNK_SIZE: usize = 8 * 1024 * 1024; // 8 MB
+/// Size of the first block, next ones grow with the loaded input up to CHUNK_SIZE
+const FIRST_CHUNK_SIZE: usize = 64 * 1024;
 
 impl<W: World + Default + Clone + Serialize + DeserializeOwned> Replay<W> {
     /// Create a new replay with given initial state
@@ -77,6 +104,13 @@ impl<W: World + Default + Clone + Serialize + DeserializeOwned> Replay<W> {
         Ok(())
     }
 
+    /// Keep only turns before the given one. Inputs at the `turn` and after it
+    /// are dropped and the replay ends at the `turn`.
+    pub fn truncate(&mut self, turn: Turn) {
+        self.inputs.retain(|(t, _)| *t < turn);
+        self.total_turns = self.total_turns.min(turn);
+    }
+
     /// Write down bytes of replay into the file located at given [path]
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let f = File::create(path)?;
@@ -84,73 +118,57 @@ impl<W: World + Default + Clone + Serialize + DeserializeOwned> Replay<W> {
         Ok(())
     }
 
-    /// Load replay from file
+    /// Load replay from file with default [`DecodeLimits`]
     pub fn load<P: AsRef<Path> + Clone>(path: P) -> ResultOwned<Self> {
-        let mut f = File::open(path.clone())?;
-        let mut last_needed: Option<Needed> = None;
-        let mut buff: Vec<u8> = Vec::new();
-        loop {
-            const CHUNK_SIZE: usize = 8 * 1014 * 1024; // 8 MB
-            let mut chunk: Vec<u8> = vec![0; CHUNK_SIZE];
-
-            let n = f.read(&mut chunk)?;
-            if n == 0 {
-                if let Some(needed) = last_needed {
-                    log::error!(
-                        "Cannot parse replay from {:?}, missing {needed:?}",
-                        path.as_ref().to_str()
-                    );
-                    return Err(GenericError::Incomplete(needed));
-                }
-            }
-            buff.extend_from_slice(&chunk[0..n]); // TODO: implement truly incremental parser for large replays
-            match Self::parser(&buff) {
-                Ok((_, value)) => return Ok(value),
-                Err(Err::Incomplete(needed)) => {
-                    last_needed = Some(needed);
-                }
-                Err(Err::Error(e)) => return Err(e.into_owned()),
-                Err(Err::Failure(e)) => return Err(e.into_owned()),
-            }
-        }
+        Self::load_with_limits(path, DecodeLimits::default())
+    }
+
+    /// Load replay from file rejecting replays that exceed the limits
+    pub fn load_with_limits<P: AsRef<Path> + Clone>(
+        path: P,
+        limits: DecodeLimits,
+    ) -> ResultOwned<Self> {
+        load_file(path, |input| Self::parser(input, limits))
     }
 
     /// Write down serialized bytes of replay into the buffer
     pub fn encode<S: Write>(&self, mut sink: S) -> Result<()> {
-        sink.write_all(&MAGIC_BYTES)?;
-        encode_be_u32(REPLAY_FORMAT_VERSION, &mut sink)?;
-        sink.write_all(&W::magic_bytes())?;
-        encode_be_u32(W::current_version(), &mut sink)?;
+        self.encode_head(&mut sink)?;
+        for turn in self.inputs.iter() {
+            encode_turn(turn, &mut sink)?;
+        }
+        Ok(())
+    }
+
+    /// Write down everything before the turns with inputs
+    fn encode_head<S: Write>(&self, mut sink: S) -> Result<'_, ()> {
+        encode_header::<W, _>(&mut sink)?;
         encode_be_u32(self.rate, &mut sink)?;
         length_encoded(&mut sink, |sink| ciborium_into_writer(&self.initial, sink))?;
         encode_be_u64(self.total_turns, &mut sink)?;
-        encode_vec(&self.inputs, &mut sink, |mut sink, (step, inputs)| {
-            encode_be_u64(*step, &mut sink)?;
-            encode_vec(inputs, &mut sink, |sink, input| {
-                length_encoded(sink, |sink| ciborium_into_writer(input, sink))
-            })
-        })?;
+        encode_be_u64(self.inputs.len() as u64, &mut sink)?;
         Ok(())
     }
 
+    /// Decode replay with default [`DecodeLimits`]
     pub fn decode(bytes: &[u8]) -> Result<Self> {
-        match Self::parser(bytes) {
-            Ok((_, value)) => Ok(value),
-            Err(Err::Incomplete(needed)) => Err(Error::Incomplete(needed)),
-            Err(Err::Error(e)) => Err(e),
-            Err(Err::Failure(e)) => Err(e),
-        }
+        Self::decode_with_limits(bytes, DecodeLimits::default())
     }
 
-    fn parser(input: &[u8]) -> Parser<Self> {
-        let (input, _) = context("core magic bytes", parse_magic)(input)?;
-        let (input, _) = context("core version", parse_core_version)(input)?;
-        let (input, _) = context("game magic bytes", parse_game_magic::<W>)(input)?;
-        let (input, _) = context("game version", parse_game_version::<W>)(input)?;
-        let (input, rate) = context("simulation rate", be_u32)(input)?;
-        let (input, initial) = context("initial world", length_decoding(ciborium_parse))(input)?;
+    /// Decode replay rejecting replays that exceed the limits
+    pub fn decode_with_limits(bytes: &[u8], limits: DecodeLimits) -> Result<'_, Self> {
+        finish_parsing(Self::parser(bytes, limits))
+    }
+
+    fn parser(input: &[u8], limits: DecodeLimits) -> Parser<Self> {
+        let (input, _) = parse_header::<W>(input)?;
+        let (input, rate) = context("simulation rate", parse_rate)(input)?;
+        let (input, initial) = context(
+            "initial world",
+            length_decoding(limits.max_initial_size, ciborium_parse),
+        )(input)?;
         let (input, total_turns) = context("total_turns", be_u64)(input)?;
-        let (input, inputs) = context("inputs", decode_vec(parse_turn::<W>))(input)?;
+        let (input, inputs) = parse_inputs(input, total_turns, limits)?;
         Ok((
             input,
             Replay {
@@ -163,6 +181,146 @@ impl<W: World + Default + Clone + Serialize + DeserializeOwned> Replay<W> {
     }
 }
 
+/// Load file incrementally with the given parser
+fn load_file<P, T, F>(path: P, parser: F) -> ResultOwned<T>
+where
+    P: AsRef<Path>,
+    F: for<'a> Fn(&'a [u8]) -> Parser<'a, T>,
+{
+    let mut f = File::open(path.as_ref())?;
+    let mut input = StreamInput::default();
+    loop {
+        let chunk = input.chunk_size() as u64;
+        let n = (&mut f).take(chunk).read_to_end(&mut input.buff)?;
+        if let Some(result) = input.parse(&parser, n == 0) {
+            return result.map_err(|e| log_incomplete(e, path.as_ref()));
+        }
+    }
+}
+
+/// Report file that ended before the parser got all the data it needs
+fn log_incomplete(e: ErrorOwned, path: &Path) -> ErrorOwned {
+    if let GenericError::Incomplete(needed) = &e {
+        log::error!("Cannot parse {path:?}, missing {needed:?}");
+    }
+    e
+}
+
+/// Input of streaming parser that is loaded piece by piece
+#[derive(Debug, Default)]
+struct StreamInput {
+    buff: Vec<u8>,
+    /// Length of input the last incomplete run of the parser asked for. The
+    /// parser isn't rerun over the whole input until that much is loaded.
+    needed: usize,
+}
+
+impl StreamInput {
+    /// Amount of bytes to read next. Grows with the loaded input, so large
+    /// files take few runs of the parser, while small ones don't allocate
+    /// a whole chunk.
+    fn chunk_size(&self) -> usize {
+        self.buff
+            .len()
+            .max(self.needed.saturating_sub(self.buff.len()))
+            .clamp(FIRST_CHUNK_SIZE, CHUNK_SIZE)
+    }
+
+    /// Run parser over loaded input. Returns `None` if more input is needed
+    /// and the end of input is not reached yet.
+    fn parse<T, F>(&mut self, parser: &F, eof: bool) -> Option<ResultOwned<T>>
+    where
+        F: for<'a> Fn(&'a [u8]) -> Parser<'a, T>,
+    {
+        if !eof && self.buff.len() < self.needed {
+            return None;
+        }
+        match parser(&self.buff) {
+            Ok((_, value)) => Some(Ok(value)),
+            Err(Err::Incomplete(needed)) if eof => Some(Err(GenericError::Incomplete(needed))),
+            Err(Err::Incomplete(needed)) => {
+                let more = match needed {
+                    Needed::Size(n) => n.get(),
+                    Needed::Unknown => 1,
+                };
+                self.needed = self.buff.len().saturating_add(more);
+                None
+            }
+            Err(Err::Error(e)) => Some(Err(e.into_owned())),
+            Err(Err::Failure(e)) => Some(Err(e.into_owned())),
+        }
+    }
+}
+
+/// Convert result of top level parser to the result of decoding
+fn finish_parsing<T>(result: Parser<T>) -> Result<T> {
+    match result {
+        Ok((_, value)) => Ok(value),
+        Err(Err::Incomplete(needed)) => Err(Error::Incomplete(needed)),
+        Err(Err::Error(e)) => Err(e),
+        Err(Err::Failure(e)) => Err(e),
+    }
+}
+
+/// Write down magic bytes and versions of format and game
+fn encode_header<'a, W: World, S: Write>(sink: S) -> Result<'a, ()> {
+    encode_generic_header(W::magic_bytes(), W::current_version(), sink)
+}
+
+/// Write down magic bytes and version of format with the given game magic
+/// bytes and version
+fn encode_generic_header<'a, S: Write>(
+    game_magic: [u8; 4],
+    game_version: u32,
+    mut sink: S,
+) -> Result<'a, ()> {
+    sink.write_all(&MAGIC_BYTES)?;
+    encode_be_u32(REPLAY_FORMAT_VERSION, &mut sink)?;
+    sink.write_all(&game_magic)?;
+    encode_be_u32(game_version, &mut sink)?;
+    Ok(())
+}
+
+/// Check magic bytes and versions of format and game
+fn parse_header<W: World>(input: &[u8]) -> Parser<'_, ()> {
+    let (input, _) = context("core magic bytes", parse_magic)(input)?;
+    let (input, _) = context("core version", parse_core_version)(input)?;
+    let (input, _) = context("game magic bytes", parse_game_magic::<W>)(input)?;
+    let (input, _) = context("game version", parse_game_version::<W>)(input)?;
+    Ok((input, ()))
+}
+
+/// Check magic bytes and version of format, game magic bytes and version
+/// are returned as is.
+fn parse_generic_header(input: &[u8]) -> Parser<'_, ([u8; 4], u32)> {
+    let (input, _) = context("core magic bytes", parse_magic)(input)?;
+    let (input, _) = context("core version", parse_core_version)(input)?;
+    let (input, magic) = context("game magic bytes", take(4_u32))(input)?;
+    let (input, version) = context("game version", be_u32)(input)?;
+    let mut magic_buff = [0; 4];
+    magic_buff.copy_from_slice(magic);
+    Ok((input, (magic_buff, version)))
+}
+
+/// Game magic bytes as text. Bytes that are not printable UTF-8 are written
+/// in hex like `0x00ff1234`.
+fn magic_to_text(magic: [u8; 4]) -> String {
+    match std::str::from_utf8(&magic) {
+        Ok(text) if !text.chars().any(char::is_control) => text.to_owned(),
+        _ => format!("0x{:08x}", u32::from_be_bytes(magic)),
+    }
+}
+
+/// Parse game magic bytes written by [`magic_to_text`]
+#[cfg(feature = "text")]
+fn magic_from_text(text: &str) -> Option<[u8; 4]> {
+    if let Ok(magic) = text.as_bytes().try_into() {
+        return Some(magic);
+    }
+    let hex = text.strip_prefix("0x").filter(|hex| hex.len() == 8)?;
+    u32::from_str_radix(hex, 16).ok().map(u32::to_be_bytes)
+}
+
 fn parse_magic(input: &[u8]) -> Parser<()> {
     let (input, magic) = take(4_u32)(input)?;
     if magic != MAGIC_BYTES {
@@ -185,6 +343,16 @@ fn parse_game_magic<W: World>(input: &[u8]) -> Parser<()> {
     }
 }
 
+/// Simulation rate is a divisor of turn duration, so zero is rejected
+fn parse_rate(input: &[u8]) -> Parser<'_, u32> {
+    let (input, rate) = be_u32(input)?;
+    if rate == 0 {
+        Err(Err::Failure(Error::ZeroRate))
+    } else {
+        Ok((input, rate))
+    }
+}
+
 fn parse_core_version(input: &[u8]) -> Parser<u32> {
     let (input, version) = be_u32(input)?;
     if version != REPLAY_FORMAT_VERSION {
@@ -203,14 +371,74 @@ fn parse_game_version<W: World>(input: &[u8]) -> Parser<u32> {
     }
 }
 
-fn parse_turn<W: World>(input: &[u8]) -> Parser<(u64, Vec<W::Input>)> {
+fn encode_turn<'a, I: Serialize, S: Write>(
+    (turn, inputs): &(Turn, Vec<I>),
+    mut sink: S,
+) -> Result<'a, ()> {
+    encode_be_u64(*turn, &mut sink)?;
+    encode_vec(inputs, &mut sink, |sink, input| {
+        length_encoded(sink, |sink| ciborium_into_writer(input, sink))
+    })
+}
+
+/// Parse turns with inputs checking that turns are strictly increasing
+fn parse_inputs<I: DeserializeOwned>(
+    input: &[u8],
+    total_turns: Turn,
+    limits: DecodeLimits,
+) -> Parser<'_, Vec<(Turn, Vec<I>)>> {
+    parse_turns(input, total_turns, limits, |input| {
+        parse_turn::<I>(input, limits)
+    })
+}
+
+/// Parse turns with the given parser of a single turn. Turns are checked to
+/// be strictly increasing, so they can be binary searched.
+fn parse_turns<'a, T, F>(
+    input: &'a [u8],
+    total_turns: Turn,
+    limits: DecodeLimits,
+    turn_parser: F,
+) -> Parser<'a, Vec<(Turn, T)>>
+where
+    F: FnMut(&'a [u8]) -> Parser<'a, (Turn, T)> + Copy,
+{
+    if total_turns > limits.max_turns {
+        return Err(Err::Failure(Error::TooManyTurns(
+            total_turns,
+            limits.max_turns,
+        )));
+    }
+    let (input, turns) = context("inputs", decode_vec(limits.max_turns, turn_parser))(input)?;
+    let mut last_turn = None;
+    for (turn, _) in turns.iter() {
+        if let Some(last_turn) = last_turn.filter(|last| last >= turn) {
+            return Err(Err::Failure(Error::IncoherentTurn(last_turn, *turn)));
+        }
+        last_turn = Some(*turn);
+    }
+    Ok((input, turns))
+}
+
+fn parse_turn<I: DeserializeOwned>(
+    input: &[u8],
+    limits: DecodeLimits,
+) -> Parser<'_, (u64, Vec<I>)> {
     let (input, turn) = context("turn number", be_u64)(input)?;
-    let (input, inputs) = context("turn inputs", decode_vec(parse_input::<W>))(input)?;
+    let (input, inputs) = context(
+        "turn inputs",
+        decode_vec(limits.max_inputs_per_turn, |input| {
+            parse_input::<I>(input, limits)
+        }),
+    )(input)?;
     Ok((input, (turn, inputs)))
 }
 
-fn parse_input<W: World>(input: &[u8]) -> Parser<W::Input> {
-    let (input, input_opt) = context("turn input", length_decoding(ciborium_parse))(input)?;
+fn parse_input<I: DeserializeOwned>(input: &[u8], limits: DecodeLimits) -> Parser<'_, I> {
+    let (input, input_opt) = context(
+        "turn input",
+        length_decoding(limits.max_input_size, ciborium_parse),
+    )(input)?;
     if let Some(turn_input) = input_opt {
         Ok((input, turn_input))
     } else {
@@ -221,7 +449,10 @@ fn parse_input<W: World>(input: &[u8]) -> Parser<W::Input> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{TestInput, TestWorld};
+    use proptest::{collection::vec, prelude::*};
     use serde::{Deserialize, Serialize};
+    use std::cell::Cell;
     use std::fmt::Debug;
     use test_log::test;
 
@@ -299,6 +530,27 @@ mod tests {
         make_encode_decode_test(replay6);
     }
 
+    #[test]
+    fn truncate_test() {
+        let mut replay = Replay::<TestWorld2>::new(&TestWorld2 { field1: 42 }, 60);
+        replay.record(0, &[]).expect("record");
+        replay.record(2, &[TestInput2::Add(4)]).expect("record");
+        replay.record(5, &[TestInput2::Sub(2)]).expect("record");
+
+        let mut truncated = replay.clone();
+        truncated.truncate(10);
+        assert_eq!(truncated, replay);
+
+        truncated.truncate(5);
+        assert_eq!(truncated.total_turns, 5);
+        assert_eq!(truncated.inputs.len(), 2);
+        truncated.record(5, &[TestInput2::Add(1)]).expect("record");
+
+        truncated.truncate(0);
+        assert_eq!(truncated.total_turns, 0);
+        assert!(truncated.inputs.is_empty());
+    }
+
     #[test]
     fn save_load_test() {
         let mut replay1 = Replay::<TestWorld2>::new(&TestWorld2 { field1: 42 }, 60);
@@ -312,6 +564,239 @@ mod tests {
         make_save_load_test(replay1);
     }
 
+    #[test]
+    fn stream_input_waits_for_needed_bytes() {
+        let mut replay = Replay::<TestWorld2>::new(&TestWorld2 { field1: 42 }, 60);
+        replay
+            .record(3, &[TestInput2::Add(4), TestInput2::Sub(2)])
+            .expect("record");
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+
+        fn counted(
+            runs: &Cell<usize>,
+        ) -> impl for<'a> Fn(&'a [u8]) -> Parser<'a, Replay<TestWorld2>> + '_ {
+            |input| {
+                runs.set(runs.get() + 1);
+                Replay::parser(input, DecodeLimits::default())
+            }
+        }
+        let runs = Cell::new(0);
+        let parser = counted(&runs);
+        // Bytes arrive one by one, the parser runs only when its request is satisfied
+        let mut input = StreamInput::default();
+        let mut result = None;
+        for byte in buffer.iter() {
+            input.buff.push(*byte);
+            result = input.parse(&parser, false);
+            if result.is_some() {
+                break;
+            }
+        }
+        assert_eq!(result.expect("parsed").expect("decoded"), replay);
+        assert!(runs.get() < buffer.len() / 2);
+
+        let mut input = StreamInput::default();
+        input.buff.extend_from_slice(&buffer[..buffer.len() - 1]);
+        assert!(input.parse(&parser, false).is_none());
+        assert!(matches!(
+            input.parse(&parser, true),
+            Some(Err(GenericError::Incomplete(_)))
+        ));
+    }
+
+    #[test]
+    fn decode_limits_test() {
+        let mut replay = Replay::<TestWorld2>::new(&TestWorld2 { field1: 42 }, 60);
+        replay
+            .record(1, &[TestInput2::Add(4), TestInput2::Sub(2)])
+            .expect("record");
+        replay.record(2, &[TestInput2::Add(8)]).expect("record");
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+
+        let decode = |limits| Replay::<TestWorld2>::decode_with_limits(&buffer, limits);
+        assert_eq!(decode(DecodeLimits::unlimited()).expect("decoded"), replay);
+        let limits = DecodeLimits::default();
+        assert!(matches!(
+            root_cause(
+                decode(DecodeLimits {
+                    max_turns: 1,
+                    ..limits
+                })
+                .unwrap_err()
+            ),
+            Error::TooManyTurns(2, 1)
+        ));
+        assert!(matches!(
+            root_cause(
+                decode(DecodeLimits {
+                    max_inputs_per_turn: 1,
+                    ..limits
+                })
+                .unwrap_err()
+            ),
+            Error::TooManyItems(2, 1)
+        ));
+        assert!(matches!(
+            root_cause(
+                decode(DecodeLimits {
+                    max_input_size: 1,
+                    ..limits
+                })
+                .unwrap_err()
+            ),
+            Error::BlockTooLarge(_, 1)
+        ));
+        assert!(matches!(
+            root_cause(
+                decode(DecodeLimits {
+                    max_initial_size: 1,
+                    ..limits
+                })
+                .unwrap_err()
+            ),
+            Error::BlockTooLarge(_, 1)
+        ));
+    }
+
+    #[test]
+    fn corrupted_lengths_test() {
+        let replay = Replay::<TestWorld2>::new(&TestWorld2 { field1: 42 }, 60);
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+
+        // Amount of turns with inputs is the last field of empty replay
+        let len = buffer.len();
+        buffer[len - 8..].copy_from_slice(&u64::MAX.to_be_bytes());
+        let result = Replay::<TestWorld2>::decode_with_limits(&buffer, DecodeLimits::unlimited());
+        assert!(matches!(
+            root_cause(result.unwrap_err()),
+            Error::Incomplete(_)
+        ));
+        let result = Replay::<TestWorld2>::decode(&buffer);
+        assert!(matches!(
+            root_cause(result.unwrap_err()),
+            Error::TooManyItems(u64::MAX, _)
+        ));
+
+        let mut replay = Replay::<TestWorld2>::new(&TestWorld2 { field1: 42 }, 60);
+        replay.total_turns = 5;
+        replay.inputs = vec![(3, vec![]), (1, vec![])];
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+        let result = Replay::<TestWorld2>::decode(&buffer);
+        assert!(matches!(
+            root_cause(result.unwrap_err()),
+            Error::IncoherentTurn(3, 1)
+        ));
+
+        let replay = Replay::<TestWorld2>::new(&TestWorld2 { field1: 42 }, 0);
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+        let result = Replay::<TestWorld2>::decode(&buffer);
+        assert!(matches!(root_cause(result.unwrap_err()), Error::ZeroRate));
+    }
+
+    fn arb_world() -> impl Strategy<Value = TestWorld> {
+        (any::<u32>(), vec(".*", 0..4)).prop_map(|(field1, names)| TestWorld { field1, names })
+    }
+
+    fn arb_input() -> impl Strategy<Value = TestInput> {
+        let leaf = prop_oneof![
+            any::<u32>().prop_map(TestInput::Add),
+            ".*".prop_map(TestInput::Say),
+            any::<(i32, i32)>().prop_map(|(x, y)| TestInput::Move { x, y }),
+            Just(TestInput::End),
+        ];
+        leaf.prop_recursive(3, 16, 4, |inner| {
+            vec(inner, 0..4).prop_map(TestInput::Batch)
+        })
+    }
+
+    /// Replay with strictly increasing turns and `total_turns` covering them
+    fn arb_replay() -> impl Strategy<Value = Replay<TestWorld>> {
+        (
+            1..=u32::MAX,
+            arb_world(),
+            vec((0..1000_u64, vec(arb_input(), 0..4)), 0..32),
+            0..100_u64,
+        )
+            .prop_map(|(rate, initial, turns, extra)| {
+                let mut replay = Replay::new(&initial, rate);
+                let mut turn = 0;
+                for (gap, inputs) in turns {
+                    turn += gap;
+                    replay.record(turn, &inputs).expect("increasing turns");
+                    turn += 1;
+                }
+                replay.total_turns += extra;
+                replay
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_encode_decode_id(replay in arb_replay()) {
+            let mut buffer = vec![];
+            replay.encode(&mut buffer).expect("encoded");
+            prop_assert_eq!(Replay::decode(&buffer).expect("decoded"), replay);
+        }
+
+        #[test]
+        fn prop_record_rejects_non_monotonic(replay in arb_replay(), back in 0..1000_u64) {
+            prop_assume!(!replay.inputs.is_empty());
+            let last = replay.inputs.last().unwrap().0;
+            let turn = last.saturating_sub(back);
+            let mut recorded = replay.clone();
+            let result = recorded.record(turn, &[TestInput::Add(1)]);
+            prop_assert!(matches!(result, Err(Error::IncoherentTurn(l, t)) if l == last && t == turn));
+            prop_assert_eq!(recorded, replay);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(4))]
+
+        #[test]
+        fn prop_large_save_load(replay in arb_replay(), pattern in vec(arb_input(), 1..8)) {
+            // Repeat the pattern until the replay doesn't fit into one chunk of `load`
+            let mut replay = replay;
+            let mut buffer = vec![];
+            replay.encode(&mut buffer).expect("encoded");
+            let pattern_size = {
+                let mut pattern_buffer = vec![];
+                encode_vec(&pattern, &mut pattern_buffer, |sink, input| {
+                    length_encoded(sink, |sink| ciborium_into_writer(input, sink))
+                })
+                .expect("encoded");
+                pattern_buffer.len() + 8
+            };
+            let repeats = (CHUNK_SIZE + CHUNK_SIZE / 2 - buffer.len()) / pattern_size + 1;
+            let start = replay.total_turns + 1;
+            for turn in start..start + repeats as u64 {
+                replay.record(turn, &pattern).expect("record");
+            }
+            let mut buffer = vec![];
+            replay.encode(&mut buffer).expect("encoded");
+            prop_assert!(buffer.len() > CHUNK_SIZE);
+
+            let t = temp_file::TempFile::new().expect("temp file");
+            replay.save(t.path()).expect("save replay");
+            prop_assert_eq!(std::fs::read(t.path()).expect("read"), buffer.clone());
+            let loaded = Replay::<TestWorld>::load(t.path()).expect("load replay");
+            prop_assert_eq!(&loaded, &Replay::decode(&buffer).expect("decoded"));
+            prop_assert_eq!(loaded, replay);
+        }
+    }
+
+    fn root_cause(err: Error) -> Error {
+        match err {
+            Error::Context(_, inner) => root_cause(*inner),
+            other => other,
+        }
+    }
+
     fn make_encode_decode_test<
         W: World + Clone + PartialEq + Default + Debug + Serialize + DeserializeOwned,
     >(
@@ -0,0 +1,157 @@
+use super::Replay;
+use crate::{Tick, Turn, World};
+
+/// Simulation of replay that is advanced turn by turn.
+///
+/// Each step applies inputs of the current turn and simulates it. Playback
+/// stops when the last input of a turn asks to exit or at `total_turns`,
+/// which inputs are applied, but the turn itself is not simulated.
+#[derive(Debug, Clone)]
+pub struct Playback<'a, W: World> {
+    replay: &'a Replay<W>,
+    state: W,
+    turn: Turn,
+    /// Index of the first turn with inputs that are not applied yet
+    next_input: usize,
+    stopped: bool,
+}
+
+impl<'a, W: World + Clone> Playback<'a, W> {
+    /// Start playback from the initial state of the replay
+    pub fn new(replay: &'a Replay<W>) -> Self {
+        Playback::resume(replay, replay.initial.clone(), 0)
+    }
+
+    /// Continue playback from the state of simulation at the given turn. Inputs
+    /// of the turn are not applied yet.
+    pub fn resume(replay: &'a Replay<W>, state: W, turn: Turn) -> Self {
+        Playback {
+            replay,
+            state,
+            turn,
+            next_input: replay.inputs.partition_point(|(t, _)| *t < turn),
+            stopped: false,
+        }
+    }
+}
+
+impl<'a, W: World> Playback<'a, W> {
+    pub fn replay(&self) -> &'a Replay<W> {
+        self.replay
+    }
+
+    pub fn state(&self) -> &W {
+        &self.state
+    }
+
+    pub fn into_state(self) -> W {
+        self.state
+    }
+
+    /// Turn that is simulated by the next step
+    pub fn turn(&self) -> Turn {
+        self.turn
+    }
+
+    /// Playback reached the end of replay or was stopped by an input
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Inputs of the current turn are applied, which happens when the playback
+    /// is stopped at the turn.
+    pub fn inputs_applied(&self) -> bool {
+        self.next_input
+            .checked_sub(1)
+            .is_some_and(|i| self.replay.inputs[i].0 == self.turn)
+    }
+
+    /// Apply inputs of the current turn and simulate it. Returns `false` if
+    /// the playback is stopped and the turn is not simulated.
+    pub fn step<I, S, E>(&mut self, mut input_handler: I, mut simulate: S) -> Result<bool, E>
+    where
+        I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+        S: FnMut(&mut W, Tick) -> Result<(), E>,
+    {
+        if self.stopped {
+            return Ok(false);
+        }
+        if let Some((input_turn, inputs)) = self.replay.inputs.get(self.next_input) {
+            if *input_turn == self.turn {
+                self.next_input += 1;
+                let mut need_exit = false;
+                for input in inputs {
+                    need_exit = input_handler(&mut self.state, input)?;
+                }
+                self.stopped = need_exit;
+            }
+        }
+        if self.turn >= self.replay.total_turns {
+            self.stopped = true;
+        }
+        if self.stopped {
+            return Ok(false);
+        }
+        simulate(&mut self.state, Tick::new(self.turn, self.replay.rate))?;
+        self.turn += 1;
+        Ok(true)
+    }
+
+    /// Step until the given turn is reached or the playback is stopped
+    pub fn advance_to<I, S, E>(
+        &mut self,
+        turn: Turn,
+        mut input_handler: I,
+        mut simulate: S,
+    ) -> Result<(), E>
+    where
+        I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+        S: FnMut(&mut W, Tick) -> Result<(), E>,
+    {
+        while self.turn < turn && self.step(&mut input_handler, &mut simulate)? {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{input_handler, simulate, CounterInput, CounterWorld};
+
+    #[test]
+    fn last_input_stops_playback() {
+        let mut replay = Replay::new(&CounterWorld::default(), 60);
+        replay
+            .record(1, &[CounterInput::End, CounterInput::Add(1)])
+            .expect("record");
+        replay
+            .record(2, &[CounterInput::Add(2), CounterInput::End])
+            .expect("record");
+        replay.total_turns = 5;
+
+        let mut playback = Playback::new(&replay);
+        playback.advance_to(5, input_handler, simulate).unwrap();
+        assert!(playback.is_stopped());
+        assert!(playback.inputs_applied());
+        assert_eq!(playback.turn(), 2);
+        assert_eq!(playback.state(), &CounterWorld { value: 3, ticks: 2 });
+        assert_eq!(playback.step(input_handler, simulate), Ok(false));
+    }
+
+    #[test]
+    fn resume_matches_playback() {
+        let mut replay = Replay::new(&CounterWorld::default(), 60);
+        replay.record(1, &[CounterInput::Add(1)]).expect("record");
+        replay.record(3, &[CounterInput::Add(2)]).expect("record");
+        replay.total_turns = 4;
+
+        let mut full = Playback::new(&replay);
+        full.advance_to(3, input_handler, simulate).unwrap();
+        let mut resumed = Playback::resume(&replay, full.state().clone(), 3);
+        full.advance_to(10, input_handler, simulate).unwrap();
+        resumed.advance_to(10, input_handler, simulate).unwrap();
+        assert_eq!(full.turn(), 4);
+        assert_eq!(resumed.turn(), 4);
+        assert_eq!(full.into_state(), resumed.into_state());
+    }
+}
@@ -0,0 +1,263 @@
+use nom::{error::context, number::streaming::be_u64};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs::File, io::Write, path::Path};
+
+use super::error::{Error, Result, ResultOwned};
+use super::{
+    ciborium_into_writer, ciborium_parse, encode_be_u32, encode_be_u64, encode_header, encode_turn,
+    finish_parsing, length_decoding, length_encoded, load_file, parse_header, parse_inputs,
+    parse_rate, DecodeLimits, Parser, Replay,
+};
+use crate::{InputQueue, Turn, World};
+
+/// Snapshot of simulation in the middle of a match.
+///
+/// The save shares container with [`Replay`]: the same magic bytes, format and
+/// game version checks and length prefixed CBOR blocks.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SaveGame<W: World> {
+    /// Simulation turns per second
+    pub rate: u32,
+    /// Turn at which the save was made. The turn is not simulated yet.
+    pub turn: Turn,
+    /// Current state of simulation
+    pub world: W,
+    /// Replay from the begining of simulation till the current turn
+    pub replay: Option<Replay<W>>,
+    /// Inputs that were scheduled, but not applied yet at the moment of saving
+    pub pending: Vec<(Turn, Vec<W::Input>)>,
+}
+
+impl<W: World + Default + Clone + Serialize + DeserializeOwned> SaveGame<W> {
+    /// Create save of the world at the begining of simulation
+    pub fn new(world: &W, rate: u32) -> Self {
+        SaveGame {
+            rate,
+            turn: 0,
+            world: world.clone(),
+            replay: None,
+            pending: vec![],
+        }
+    }
+
+    /// Store inputs of the queue that are not applied yet
+    pub fn with_pending(mut self, queue: &InputQueue<W::Input>) -> Self {
+        self.pending = queue
+            .iter()
+            .map(|(turn, inputs)| (turn, inputs.to_vec()))
+            .collect();
+        self
+    }
+
+    /// Make input queue with the given delay that contains the pending inputs
+    pub fn input_queue(&self, delay: Turn) -> InputQueue<W::Input> {
+        let mut queue = InputQueue::new(delay);
+        for (turn, inputs) in self.pending.iter() {
+            queue.insert(*turn, inputs.iter().cloned());
+        }
+        queue
+    }
+
+    /// Turn of the replay from [`SaveGame::into_replay`] that corresponds
+    /// to the saved turn.
+    pub fn replay_turn(&self) -> Turn {
+        if self.replay.is_some() {
+            self.turn
+        } else {
+            0
+        }
+    }
+
+    /// Make replay that continues from the saved point. If the save contains
+    /// replay so far, it is used. Otherwise new replay starts with the saved
+    /// world and counts turns from the saved one.
+    pub fn into_replay(self) -> Replay<W> {
+        let replay_turn = self.replay_turn();
+        let mut replay = self
+            .replay
+            .unwrap_or_else(|| Replay::new(&self.world, self.rate));
+        replay.truncate(replay_turn + 1);
+        replay.total_turns = replay_turn;
+        replay
+    }
+
+    /// Write down bytes of save into the file located at given [path]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<'_, ()> {
+        let f = File::create(path)?;
+        self.encode(f)?;
+        Ok(())
+    }
+
+    /// Load save from file with default [`DecodeLimits`]
+    pub fn load<P: AsRef<Path> + Clone>(path: P) -> ResultOwned<Self> {
+        Self::load_with_limits(path, DecodeLimits::default())
+    }
+
+    /// Load save from file rejecting saves that exceed the limits
+    pub fn load_with_limits<P: AsRef<Path> + Clone>(
+        path: P,
+        limits: DecodeLimits,
+    ) -> ResultOwned<Self> {
+        load_file(path, |input| Self::parser(input, limits))
+    }
+
+    /// Write down serialized bytes of save into the buffer
+    pub fn encode<S: Write>(&self, mut sink: S) -> Result<'_, ()> {
+        encode_header::<W, _>(&mut sink)?;
+        encode_be_u32(self.rate, &mut sink)?;
+        encode_be_u64(self.turn, &mut sink)?;
+        length_encoded(&mut sink, |sink| ciborium_into_writer(&self.world, sink))?;
+        length_encoded(&mut sink, |sink| match &self.replay {
+            Some(replay) => replay.encode(sink),
+            None => Ok(()),
+        })?;
+        encode_be_u64(self.pending.len() as u64, &mut sink)?;
+        for turn in self.pending.iter() {
+            encode_turn(turn, &mut sink)?;
+        }
+        Ok(())
+    }
+
+    /// Decode save with default [`DecodeLimits`]
+    pub fn decode(bytes: &[u8]) -> Result<'_, Self> {
+        Self::decode_with_limits(bytes, DecodeLimits::default())
+    }
+
+    /// Decode save rejecting saves that exceed the limits
+    pub fn decode_with_limits(bytes: &[u8], limits: DecodeLimits) -> Result<'_, Self> {
+        finish_parsing(Self::parser(bytes, limits))
+    }
+
+    fn parser(input: &[u8], limits: DecodeLimits) -> Parser<'_, Self> {
+        let (input, _) = parse_header::<W>(input)?;
+        let (input, rate) = context("simulation rate", parse_rate)(input)?;
+        let (input, turn) = context("turn", be_u64)(input)?;
+        let (input, world) = context(
+            "world",
+            length_decoding(limits.max_initial_size, ciborium_parse),
+        )(input)?;
+        let (input, replay) = context(
+            "replay",
+            length_decoding(u64::MAX, |input| Replay::parser(input, limits)),
+        )(input)?;
+        // Pending turns are not bounded by the saved turn, only by the amount of turns
+        let (input, pending) =
+            context("pending inputs", |input| parse_inputs(input, 0, limits))(input)?;
+        if let Some(replay) = &replay {
+            if replay.total_turns > turn {
+                return Err(nom::Err::Failure(Error::IncoherentTurn(
+                    replay.total_turns,
+                    turn,
+                )));
+            }
+        }
+        Ok((
+            input,
+            SaveGame {
+                rate,
+                turn,
+                world: world.unwrap_or_default(),
+                replay,
+                pending,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{TestInput, TestWorld};
+    use test_log::test;
+
+    #[test]
+    fn encode_decode_id() {
+        let save1 = SaveGame::new(&TestWorld::new(42), 60);
+        let mut buffer = vec![];
+        save1.encode(&mut buffer).expect("encoded");
+        assert_eq!(save1, SaveGame::decode(&buffer).expect("decoded"));
+
+        let mut replay = Replay::new(&TestWorld::new(0), 60);
+        replay.record(3, &[TestInput::Add(42)]).expect("record");
+        let save2 = SaveGame {
+            rate: 60,
+            turn: 10,
+            world: TestWorld::new(42),
+            replay: Some(replay),
+            pending: vec![(11, vec![TestInput::Add(1)])],
+        };
+        let mut buffer = vec![];
+        save2.encode(&mut buffer).expect("encoded");
+        assert_eq!(save2, SaveGame::decode(&buffer).expect("decoded"));
+    }
+
+    #[test]
+    fn save_load_test() {
+        let save = SaveGame {
+            rate: 30,
+            turn: 5,
+            world: TestWorld::new(4),
+            replay: None,
+            pending: vec![],
+        };
+        let t = temp_file::TempFile::new().expect("temp file");
+        save.save(t.path()).expect("save game");
+        let loaded = SaveGame::<TestWorld>::load(t.path()).expect("load game");
+        assert_eq!(save, loaded);
+    }
+
+    #[test]
+    fn into_replay() {
+        let mut replay = Replay::new(&TestWorld::new(0), 60);
+        replay.record(3, &[TestInput::Add(42)]).expect("record");
+        let save = SaveGame {
+            rate: 60,
+            turn: 10,
+            world: TestWorld::new(42),
+            replay: Some(replay.clone()),
+            pending: vec![],
+        };
+        assert_eq!(save.replay_turn(), 10);
+        let continued = save.into_replay();
+        assert_eq!(continued.initial, replay.initial);
+        assert_eq!(continued.inputs, replay.inputs);
+        assert_eq!(continued.total_turns, 10);
+
+        let save = SaveGame::new(&TestWorld::new(42), 60);
+        let fresh = save.into_replay();
+        assert_eq!(fresh.initial, TestWorld::new(42));
+        assert_eq!(fresh.total_turns, 0);
+    }
+
+    #[test]
+    fn pending_inputs_are_restored() {
+        let mut world = TestWorld::new(1);
+        let mut queue = InputQueue::new(2);
+        queue.schedule(4, [TestInput::Add(10)]);
+        queue.schedule(5, [TestInput::Add(20), TestInput::Add(30)]);
+        let save = SaveGame {
+            rate: 60,
+            turn: 5,
+            world: world.clone(),
+            replay: None,
+            pending: vec![],
+        }
+        .with_pending(&queue);
+        let mut buffer = vec![];
+        save.encode(&mut buffer).expect("encoded");
+        let loaded = SaveGame::<TestWorld>::decode(&buffer).expect("decoded");
+        assert_eq!(save, loaded);
+
+        let mut restored = loaded.input_queue(2);
+        assert_eq!(restored, queue);
+        for turn in loaded.turn..loaded.turn + 3 {
+            for input in restored.take(turn) {
+                if let TestInput::Add(value) = input {
+                    world.field1 += value;
+                }
+            }
+        }
+        assert!(restored.is_empty());
+        assert_eq!(world.field1, 61);
+    }
+}
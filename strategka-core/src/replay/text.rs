@@ -0,0 +1,239 @@
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+use super::error::{Error, Result, ResultOwned};
+use super::{magic_from_text, magic_to_text, Replay};
+use crate::{Turn, World};
+
+/// Text representation of replay in RON format. Available with the `text`
+/// feature.
+///
+/// Replay of the `circles` example as written by its `convert` command:
+///
+/// ```ron
+/// (
+///     game: "crls",
+///     version: 4,
+///     rate: 60,
+///     total_turns: 120,
+///     initial: (
+///         width: 800,
+///         height: 600,
+///         circles: [
+///             (
+///                 pos: (
+///                     x: 429496729600,
+///                     y: 343597383680,
+///                 ),
+///                 vel: (
+///                     x: 0,
+///                     y: 0,
+///                 ),
+///                 radius: 64424509440,
+///                 target: None,
+///             ),
+///         ],
+///         rng: (
+///             state: 1,
+///             inc: 3,
+///         ),
+///     ),
+///     inputs: {
+///         10: [
+///             Move(0, (
+///                 x: 858993459200,
+///                 y: 429496729600,
+///             )),
+///         ],
+///     },
+/// )
+/// ```
+///
+/// Inputs may go past `total_turns` in hand written scenarios, the replay is
+/// extended to the last turn with inputs then.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "W: Serialize, W::Input: Serialize",
+    deserialize = "W: DeserializeOwned, W::Input: DeserializeOwned"
+))]
+pub struct TextReplay<W: World> {
+    /// Magic bytes of the game as string, in hex like `0x00ff1234` if they
+    /// are not printable
+    pub game: String,
+    /// Version of the game
+    pub version: u32,
+    /// Simulation turns per second
+    pub rate: u32,
+    /// Amount of turns until the simulation should run, raised to the last
+    /// turn with inputs on conversion
+    pub total_turns: u64,
+    /// Initial state of simulation to start with
+    pub initial: W,
+    /// Inputs by turns
+    #[serde(default)]
+    pub inputs: BTreeMap<Turn, Vec<W::Input>>,
+}
+
+impl<W: World + Default + Clone + Serialize + DeserializeOwned> From<&Replay<W>> for TextReplay<W> {
+    fn from(replay: &Replay<W>) -> Self {
+        TextReplay {
+            game: magic_to_text(W::magic_bytes()),
+            version: W::current_version(),
+            rate: replay.rate,
+            total_turns: replay.total_turns,
+            initial: replay.initial.clone(),
+            inputs: replay.inputs.iter().cloned().collect(),
+        }
+    }
+}
+
+impl<W: World + Default + Clone + Serialize + DeserializeOwned> TextReplay<W> {
+    /// Check the header and convert into binary replay. `total_turns` is
+    /// raised to the last turn with inputs.
+    pub fn into_replay<'a>(self) -> Result<'a, Replay<W>> {
+        let magic = magic_from_text(&self.game).unwrap_or_else(|| {
+            let mut magic = [0; 4];
+            let len = self.game.len().min(4);
+            magic[..len].copy_from_slice(&self.game.as_bytes()[..len]);
+            magic
+        });
+        if magic != W::magic_bytes() {
+            return Err(Error::InvalidMagic(magic));
+        }
+        if !W::guard_version(self.version) {
+            return Err(Error::UnsupportedGameVersion(self.version));
+        }
+        if self.rate == 0 {
+            return Err(Error::ZeroRate);
+        }
+        let last_turn = self.inputs.keys().last().copied().unwrap_or(0);
+        Ok(Replay {
+            rate: self.rate,
+            initial: self.initial,
+            total_turns: self.total_turns.max(last_turn),
+            inputs: self.inputs.into_iter().collect(),
+        })
+    }
+}
+
+impl<W: World + Default + Clone + Serialize + DeserializeOwned> Replay<W> {
+    /// Render replay into RON text
+    pub fn to_text(&self) -> Result<'_, String> {
+        let text = ron::ser::to_string_pretty(&TextReplay::from(self), PrettyConfig::new())?;
+        Ok(text)
+    }
+
+    /// Parse replay from RON text
+    pub fn from_text(text: &str) -> Result<'_, Self> {
+        let text_replay: TextReplay<W> = ron::from_str(text)?;
+        text_replay.into_replay()
+    }
+
+    /// Write down replay as text into the file located at given [path]
+    pub fn save_text<P: AsRef<Path>>(&self, path: P) -> Result<'_, ()> {
+        fs::write(path, self.to_text()?)?;
+        Ok(())
+    }
+
+    /// Load replay from text file
+    pub fn load_text<P: AsRef<Path>>(path: P) -> ResultOwned<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::from_text(&text).map_err(|e| e.into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::error::GenericError;
+    use crate::test_util::{TestInput, TestWorld};
+
+    #[test]
+    fn text_roundtrip() {
+        let world = TestWorld {
+            field1: 42,
+            names: vec!["a".into()],
+        };
+        let mut replay = Replay::new(&world, 60);
+        replay.record(0, &[]).expect("record");
+        replay
+            .record(3, &[TestInput::Add(1), TestInput::Move { x: -1, y: 2 }])
+            .expect("record");
+        replay.record(7, &[TestInput::End]).expect("record");
+        replay.total_turns = 9;
+
+        let text = replay.to_text().expect("text");
+        assert!(text.contains("Move("));
+        assert_eq!(Replay::from_text(&text).expect("parsed"), replay);
+
+        let mut binary = vec![];
+        replay.encode(&mut binary).expect("encoded");
+        let decoded = Replay::<TestWorld>::decode(&binary).expect("decoded");
+        assert_eq!(decoded.to_text().expect("text"), text);
+    }
+
+    #[test]
+    fn hand_written_scenario() {
+        let text = r#"
+            (
+                game: "TWDT",
+                version: 2,
+                rate: 30,
+                total_turns: 0,
+                initial: (field1: 1, names: []),
+                inputs: {
+                    2: [Add(5)],
+                    4: [Move(x: 1, y: 1), End],
+                },
+            )
+        "#;
+        let replay = Replay::<TestWorld>::from_text(text).expect("parsed");
+        assert_eq!(replay.rate, 30);
+        assert_eq!(replay.total_turns, 4);
+        assert_eq!(
+            replay.inputs,
+            vec![
+                (2, vec![TestInput::Add(5)]),
+                (4, vec![TestInput::Move { x: 1, y: 1 }, TestInput::End])
+            ]
+        );
+
+        let wrong_game = text.replace("TWDT", "ABCD");
+        assert!(matches!(
+            Replay::<TestWorld>::from_text(&wrong_game),
+            Err(GenericError::InvalidMagic(m)) if &m == b"ABCD"
+        ));
+        let wrong_version = text.replace("version: 2", "version: 1");
+        assert!(matches!(
+            Replay::<TestWorld>::from_text(&wrong_version),
+            Err(GenericError::UnsupportedGameVersion(1))
+        ));
+        assert!(matches!(
+            Replay::<TestWorld>::from_text("(game: 1)"),
+            Err(GenericError::TextDecoder(_))
+        ));
+    }
+
+    #[test]
+    fn save_load_text() {
+        let mut replay = Replay::new(&TestWorld::default(), 60);
+        replay.record(1, &[TestInput::Add(4)]).expect("record");
+        let t = temp_file::TempFile::new().expect("temp file");
+        replay.save_text(t.path()).expect("save text");
+        assert_eq!(
+            Replay::<TestWorld>::load_text(t.path()).expect("load"),
+            replay
+        );
+    }
+
+    #[test]
+    fn binary_magic() {
+        let magic = [0x00, 0xff, b'A', 0x0a];
+        assert_eq!(magic_to_text(magic), "0x00ff410a");
+        assert_eq!(magic_from_text("0x00ff410a"), Some(magic));
+        assert_eq!(magic_to_text(*b"TWDT"), "TWDT");
+        assert_eq!(magic_from_text("TWDT"), Some(*b"TWDT"));
+        assert_eq!(magic_from_text("0x00ff"), None);
+    }
+}
@@ -0,0 +1,187 @@
+use nom::{error::context, number::streaming::be_u64, Err};
+use serde::{de::DeserializeOwned, Serialize};
+use std::marker::PhantomData;
+
+use super::error::{Error, Result};
+use super::{
+    ciborium_parse, decode_vec, finish_parsing, length_decoding, parse_header, parse_rate,
+    parse_turns, DecodeLimits, Parser, Replay,
+};
+use crate::{Turn, World};
+
+/// Replay that borrows encoded bytes. The initial state and inputs are kept as
+/// raw CBOR slices and decoded only when requested, which makes scanning many
+/// replays cheap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayView<'a, W: World> {
+    /// Simulation turns per second
+    pub rate: u32,
+    /// Amount of turns until the simulation should run
+    pub total_turns: u64,
+    /// Encoded initial state of simulation
+    pub raw_initial: &'a [u8],
+    /// Encoded inputs of each turn
+    pub raw_inputs: Vec<(Turn, Vec<&'a [u8]>)>,
+    world: PhantomData<W>,
+}
+
+impl<'a, W: World + Default + Clone + Serialize + DeserializeOwned> ReplayView<'a, W> {
+    /// Parse structure of replay with default [`DecodeLimits`] without decoding
+    /// the world and inputs.
+    pub fn decode(bytes: &'a [u8]) -> Result<'a, Self> {
+        Self::decode_with_limits(bytes, DecodeLimits::default())
+    }
+
+    /// Parse structure of replay rejecting replays that exceed the limits
+    pub fn decode_with_limits(bytes: &'a [u8], limits: DecodeLimits) -> Result<'a, Self> {
+        finish_parsing(Self::parser(bytes, limits))
+    }
+
+    /// Decode initial state of simulation
+    pub fn initial(&self) -> Result<'a, W> {
+        if self.raw_initial.is_empty() {
+            return Ok(W::default());
+        }
+        finish_parsing(ciborium_parse(self.raw_initial))
+    }
+
+    /// Decode inputs recorded at the given turn. Turns without inputs give
+    /// empty vector.
+    pub fn inputs(&self, turn: Turn) -> Result<'a, Vec<W::Input>> {
+        match self.raw_inputs.binary_search_by_key(&turn, |(t, _)| *t) {
+            Ok(i) => self.raw_inputs[i]
+                .1
+                .iter()
+                .map(|raw| Self::decode_input(raw))
+                .collect(),
+            Err(_) => Ok(vec![]),
+        }
+    }
+
+    /// Decode single raw input
+    pub fn decode_input(raw: &'a [u8]) -> Result<'a, W::Input> {
+        finish_parsing(ciborium_parse(raw))
+    }
+
+    /// Decode everything into owned replay
+    pub fn to_replay(&self) -> Result<'a, Replay<W>> {
+        let inputs = self
+            .raw_inputs
+            .iter()
+            .map(|(turn, raws)| {
+                let inputs = raws
+                    .iter()
+                    .map(|raw| Self::decode_input(raw))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((*turn, inputs))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Replay {
+            rate: self.rate,
+            initial: self.initial()?,
+            total_turns: self.total_turns,
+            inputs,
+        })
+    }
+
+    fn parser(input: &'a [u8], limits: DecodeLimits) -> Parser<'a, Self> {
+        let (input, _) = parse_header::<W>(input)?;
+        let (input, rate) = context("simulation rate", parse_rate)(input)?;
+        let (input, raw_initial) = context(
+            "initial world",
+            length_decoding(limits.max_initial_size, raw_block),
+        )(input)?;
+        let (input, total_turns) = context("total_turns", be_u64)(input)?;
+        let (input, raw_inputs) = parse_turns(input, total_turns, limits, |input| {
+            parse_raw_turn(input, limits)
+        })?;
+        Ok((
+            input,
+            ReplayView {
+                rate,
+                total_turns,
+                raw_initial: raw_initial.unwrap_or_default(),
+                raw_inputs,
+                world: PhantomData,
+            },
+        ))
+    }
+}
+
+/// Take the whole length restricted block as is
+fn raw_block(input: &[u8]) -> Parser<'_, &[u8]> {
+    Ok((&input[input.len()..], input))
+}
+
+fn parse_raw_turn(input: &[u8], limits: DecodeLimits) -> Parser<'_, (Turn, Vec<&[u8]>)> {
+    let (input, turn) = context("turn number", be_u64)(input)?;
+    let (input, inputs) = context(
+        "turn inputs",
+        decode_vec(limits.max_inputs_per_turn, |input| {
+            let (input, raw) = context(
+                "turn input",
+                length_decoding(limits.max_input_size, raw_block),
+            )(input)?;
+            raw.map(|raw| (input, raw))
+                .ok_or(Err::Failure(Error::MissingTurnInput))
+        }),
+    )(input)?;
+    Ok((input, (turn, inputs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{TestInput, TestWorld};
+
+    #[test]
+    fn view_test() {
+        let mut replay = Replay::new(&TestWorld::new(42), 30);
+        replay.record(1, &[TestInput::Add(4)]).expect("record");
+        replay
+            .record(3, &[TestInput::Say("hi".into()), TestInput::Add(2)])
+            .expect("record");
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+
+        let view = ReplayView::<TestWorld>::decode(&buffer).expect("view");
+        assert_eq!(view.rate, 30);
+        assert_eq!(view.total_turns, 3);
+        assert_eq!(view.raw_inputs.len(), 2);
+        assert_eq!(view.raw_inputs[1].1.len(), 2);
+        assert_eq!(view.initial().expect("initial"), TestWorld::new(42));
+        assert_eq!(view.inputs(2).expect("inputs"), vec![]);
+        assert_eq!(
+            view.inputs(3).expect("inputs"),
+            vec![TestInput::Say("hi".into()), TestInput::Add(2)]
+        );
+        assert_eq!(view.to_replay().expect("replay"), replay);
+    }
+
+    #[test]
+    fn view_is_lazy() {
+        let mut replay = Replay::new(&TestWorld::new(42), 30);
+        replay.record(1, &[TestInput::Add(4)]).expect("record");
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+        // Corrupt the CBOR of the input, the structure is still valid
+        let len = buffer.len();
+        buffer[len - 1] = 0xff;
+
+        let view = ReplayView::<TestWorld>::decode(&buffer).expect("view");
+        assert!(view.inputs(1).is_err());
+        assert!(Replay::<TestWorld>::decode(&buffer).is_err());
+    }
+
+    #[test]
+    fn out_of_order_turns() {
+        let mut replay = Replay::new(&TestWorld::new(42), 30);
+        replay.total_turns = 5;
+        replay.inputs = vec![(3, vec![TestInput::Add(1)]), (1, vec![TestInput::Add(2)])];
+        let mut buffer = vec![];
+        replay.encode(&mut buffer).expect("encoded");
+
+        let result = ReplayView::<TestWorld>::decode(&buffer);
+        assert!(matches!(result.unwrap_err(), Error::IncoherentTurn(3, 1)));
+    }
+}
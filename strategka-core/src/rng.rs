@@ -0,0 +1,231 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::Fixed;
+
+/// Portable pseudo random generator (PCG32, XSH RR variant).
+///
+/// The algorithm is fixed and doesn't depend on platform, so the generator can
+/// live inside the simulation state. It is serialized together with the world,
+/// thus random events during simulation are replayed deterministically.
+///
+/// Independent generators for subsystems are obtained with [`Rng::split`] or
+/// by selecting a stream explicitly with [`Rng::with_stream`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rng {
+    state: u64,
+    inc: u64,
+}
+
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+const PCG_DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(0)
+    }
+}
+
+impl Rng {
+    /// Create generator with given seed at default stream
+    pub fn new(seed: u64) -> Self {
+        Rng::with_stream(seed, PCG_DEFAULT_STREAM)
+    }
+
+    /// Create generator with given seed at given stream. Generators with the
+    /// same seed and different streams produce unrelated sequences.
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Rng {
+            state: 0,
+            inc: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Make new independent generator. The state of the current one is advanced.
+    pub fn split(&mut self) -> Self {
+        let seed = self.next_u64();
+        let stream = self.next_u64();
+        Rng::with_stream(seed, stream)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(PCG_MULTIPLIER).wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let high = self.next_u32() as u64;
+        let low = self.next_u32() as u64;
+        (high << 32) | low
+    }
+
+    /// Uniformly distributed value in `[0, 1)`. Only exact float operations
+    /// are used, so the result is the same on all platforms.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Uniformly distributed value from the range without modulo bias.
+    ///
+    /// Panics if the range is empty.
+    pub fn range_u32(&mut self, range: Range<u32>) -> u32 {
+        assert!(range.start < range.end, "Empty range for random value");
+        let span = range.end - range.start;
+        let threshold = span.wrapping_neg() % span;
+        loop {
+            let r = self.next_u32();
+            if r >= threshold {
+                return range.start + r % span;
+            }
+        }
+    }
+
+    /// Uniformly distributed value from the range without modulo bias.
+    ///
+    /// Panics if the range is empty.
+    pub fn range_i32(&mut self, range: Range<i32>) -> i32 {
+        assert!(range.start < range.end, "Empty range for random value");
+        let span = range.end.abs_diff(range.start);
+        range.start.wrapping_add(self.range_u32(0..span) as i32)
+    }
+
+    /// Uniformly distributed value from the range.
+    ///
+    /// Panics if the range is empty.
+    pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        assert!(range.start < range.end, "Empty range for random value");
+        loop {
+            // Rounding can reach the end of the range, such values are drawn again
+            let v = range.start + (range.end - range.start) * self.next_f32();
+            if v < range.end {
+                return v;
+            }
+        }
+    }
+
+    /// Uniformly distributed value from the range with resolution of [`Fixed`].
+    ///
+    /// Panics if the range is empty.
+    pub fn range_fixed(&mut self, range: Range<Fixed>) -> Fixed {
+        assert!(range.start < range.end, "Empty range for random value");
+        let span = range.end.to_bits().abs_diff(range.start.to_bits());
+        let threshold = span.wrapping_neg() % span;
+        loop {
+            let r = self.next_u64();
+            if r >= threshold {
+                return Fixed::from_bits(range.start.to_bits().wrapping_add((r % span) as i64));
+            }
+        }
+    }
+
+    /// Returns `true` with probability `numerator / denominator`. Zero denominator
+    /// always gives `false` without advancing the generator.
+    pub fn chance(&mut self, numerator: u32, denominator: u32) -> bool {
+        denominator > 0 && self.range_u32(0..denominator) < numerator
+    }
+
+    /// Pick random element of the slice
+    pub fn choose<'a, T>(&mut self, values: &'a [T]) -> Option<&'a T> {
+        if values.is_empty() {
+            None
+        } else {
+            values.get(self.range_u32(0..values.len() as u32) as usize)
+        }
+    }
+
+    /// Fisher-Yates shuffle of the slice
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            let j = self.range_u32(0..i as u32 + 1) as usize;
+            values.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_sequence() {
+        // Values from the reference implementation `pcg32_srandom_r(42, 54)`
+        let mut rng = Rng::with_stream(42, 54);
+        let values: Vec<u32> = (0..6).map(|_| rng.next_u32()).collect();
+        assert_eq!(
+            values,
+            vec![0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]
+        );
+    }
+
+    #[test]
+    fn serialization_keeps_sequence() {
+        let mut rng = Rng::new(7);
+        rng.next_u64();
+        let mut buff = vec![];
+        ciborium::into_writer(&rng, &mut buff).expect("encoded");
+        let mut decoded: Rng = ciborium::from_reader(&buff[..]).expect("decoded");
+        assert_eq!(rng.next_u64(), decoded.next_u64());
+    }
+
+    #[test]
+    fn split_streams_differ() {
+        let mut rng = Rng::new(1);
+        let mut child1 = rng.split();
+        let mut child2 = rng.split();
+        assert_ne!(child1, child2);
+        assert_ne!(child1.next_u64(), child2.next_u64());
+        assert_ne!(Rng::with_stream(1, 1), Rng::with_stream(1, 2));
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = Rng::new(3);
+        for _ in 0..1000 {
+            assert!((10..20).contains(&rng.range_u32(10..20)));
+            assert!((-5..5).contains(&rng.range_i32(-5..5)));
+            let v = rng.range_f32(1.0..2.0);
+            assert!((1.0..2.0).contains(&v));
+            let f = rng.range_fixed(Fixed::from_int(-1)..Fixed::ONE);
+            assert!((Fixed::from_int(-1)..Fixed::ONE).contains(&f));
+        }
+        assert_eq!(rng.range_i32(i32::MIN..i32::MIN + 1), i32::MIN);
+        // The largest float below one times the span rounds up to the end
+        let end = 1.0 + f32::EPSILON;
+        for _ in 0..1000 {
+            assert!(rng.range_f32(1.0..end) < end);
+        }
+        assert!(!rng.chance(1, 0));
+        let mut values = [1, 2, 3, 4, 5];
+        rng.shuffle(&mut values);
+        values.sort();
+        assert_eq!(values, [1, 2, 3, 4, 5]);
+        assert_eq!(rng.choose::<u32>(&[]), None);
+    }
+
+    #[test]
+    fn full_fixed_range() {
+        let mut rng = Rng::new(3);
+        let mut negative = false;
+        let mut positive = false;
+        for _ in 0..100 {
+            let f = rng.range_fixed(Fixed::MIN..Fixed::MAX);
+            assert!(f < Fixed::MAX);
+            negative |= f < Fixed::ZERO;
+            positive |= f > Fixed::ZERO;
+        }
+        assert!(negative && positive);
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty range")]
+    fn reversed_fixed_range() {
+        Rng::new(3).range_fixed(Fixed::ONE..Fixed::from_int(-1));
+    }
+}
@@ -0,0 +1,86 @@
+//! Worlds shared by tests of the crate
+
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+
+use crate::{Tick, World};
+
+/// World that is only encoded and decoded
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TestWorld {
+    pub field1: u32,
+    pub names: Vec<String>,
+}
+
+impl TestWorld {
+    pub fn new(field1: u32) -> Self {
+        TestWorld {
+            field1,
+            names: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TestInput {
+    Add(u32),
+    Say(String),
+    Move { x: i32, y: i32 },
+    Batch(Vec<TestInput>),
+    End,
+}
+
+impl World for TestWorld {
+    type Input = TestInput;
+
+    fn magic_bytes() -> [u8; 4] {
+        *b"TWDT"
+    }
+
+    fn current_version() -> u32 {
+        2
+    }
+}
+
+/// World that is simulated by [`input_handler`] and [`simulate`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CounterWorld {
+    pub value: i64,
+    /// Amount of simulated turns
+    pub ticks: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CounterInput {
+    Add(i64),
+    /// Input without effect on the world
+    Log(String),
+    /// Asks playback to stop
+    End,
+}
+
+impl World for CounterWorld {
+    type Input = CounterInput;
+
+    fn magic_bytes() -> [u8; 4] {
+        *b"TWDC"
+    }
+
+    fn current_version() -> u32 {
+        1
+    }
+}
+
+pub fn input_handler(world: &mut CounterWorld, input: &CounterInput) -> Result<bool, Infallible> {
+    match input {
+        CounterInput::Add(v) => world.value += v,
+        CounterInput::Log(_) => (),
+        CounterInput::End => return Ok(true),
+    }
+    Ok(false)
+}
+
+pub fn simulate(world: &mut CounterWorld, _: Tick) -> Result<(), Infallible> {
+    world.ticks += 1;
+    Ok(())
+}
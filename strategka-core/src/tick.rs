@@ -0,0 +1,66 @@
+use crate::{Fixed, Turn};
+
+/// Simulation step that is passed to the simulation. Time is represented as
+/// integer turn number and constant rate, so the simulation can be written
+/// without floating point time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tick {
+    /// Number of the turn that is simulated
+    pub turn: Turn,
+    /// Simulation turns per second
+    pub rate: u32,
+}
+
+impl Tick {
+    /// Panics if the rate is zero, as the turn would have infinite duration
+    pub fn new(turn: Turn, rate: u32) -> Self {
+        assert!(rate > 0, "Simulation rate must be positive");
+        Tick { turn, rate }
+    }
+
+    /// Duration of one turn in seconds
+    pub fn dt(&self) -> Fixed {
+        Fixed::from_ratio(1, self.rate as i64)
+    }
+
+    /// Duration of one turn in seconds as float. Use it only outside of
+    /// simulation, e.g. for animations.
+    pub fn dt_f32(&self) -> f32 {
+        1.0 / self.rate as f32
+    }
+
+    /// Duration of one turn in nanoseconds
+    pub fn dt_nanos(&self) -> u64 {
+        1_000_000_000 / self.rate as u64
+    }
+
+    /// Time in seconds passed from the start of simulation till the turn
+    pub fn time(&self) -> Fixed {
+        Fixed::from_ratio(self.turn as i64, self.rate as i64)
+    }
+
+    /// Tick of the following turn
+    pub fn next(&self) -> Self {
+        Tick::new(self.turn + 1, self.rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_time() {
+        let tick = Tick::new(30, 60);
+        assert_eq!(tick.dt() * 60, Fixed::ONE - Fixed::from_bits(16));
+        assert_eq!(tick.time(), Fixed::HALF);
+        assert_eq!(tick.next().turn, 31);
+        assert_eq!(tick.dt_nanos(), 16_666_666);
+    }
+
+    #[test]
+    #[should_panic(expected = "rate must be positive")]
+    fn zero_rate() {
+        Tick::new(0, 0);
+    }
+}
@@ -0,0 +1,92 @@
+//! Compares CPU cost of preparing pixels of a frame for presentation. The old path
+//! converted every pixel of the pixmap into BGRA window surface, the new one copies
+//! premultiplied rows into the buffer of locked streaming texture. Only the copy into
+//! plain memory is measured, locking the texture and uploading it to the GPU is not.
+//!
+//! Run with `cargo bench --bench pixel_copy`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use strategka_render::present::copy_rows;
+use tiny_skia::*;
+
+const WIDTH: u32 = 1000;
+const HEIGHT: u32 = 1000;
+const FRAMES: u32 = 200;
+
+/// Presentation that `render_loop` used before streaming textures
+fn demultiply_blit(pixmap: &Pixmap, window_pixels: &mut [u8]) {
+    for (i, pixel) in pixmap.pixels().iter().enumerate() {
+        let c = pixel.demultiply();
+        window_pixels[i * 4] = c.blue();
+        window_pixels[i * 4 + 1] = c.green();
+        window_pixels[i * 4 + 2] = c.red();
+        window_pixels[i * 4 + 3] = c.alpha();
+    }
+}
+
+fn make_pixmap() -> Pixmap {
+    let mut pixmap = Pixmap::new(WIDTH, HEIGHT).expect("pixmap");
+    pixmap.fill(Color::from_rgba8(0, 0, 0, 255));
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(50, 127, 150, 200);
+    paint.anti_alias = true;
+    for i in 0..20 {
+        let path =
+            PathBuilder::from_circle(50.0 * i as f32, 40.0 * i as f32, 60.0).expect("circle");
+        pixmap.fill_path(
+            &path,
+            &paint,
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+    pixmap
+}
+
+fn measure<F: FnMut()>(name: &str, mut f: F) {
+    // Warm up caches before measuring
+    for _ in 0..FRAMES / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        f();
+    }
+    let per_frame: Duration = start.elapsed() / FRAMES;
+    println!(
+        "{name:<32} {:>10.3} ms/frame {:>10.1} frames/s",
+        per_frame.as_secs_f64() * 1000.0,
+        1.0 / per_frame.as_secs_f64()
+    );
+}
+
+fn main() {
+    let pixmap = make_pixmap();
+    let row_bytes = WIDTH as usize * 4;
+    // Rows of locked textures may be padded
+    let padded_pitch = row_bytes + 64;
+    let mut surface = vec![0u8; row_bytes * HEIGHT as usize];
+    let mut padded = vec![0u8; padded_pitch * HEIGHT as usize];
+
+    println!("copying pixels of {WIDTH}x{HEIGHT} pixmap, {FRAMES} frames");
+    measure("demultiply blit (before)", || {
+        demultiply_blit(black_box(&pixmap), black_box(&mut surface))
+    });
+    measure("row copy (after)", || {
+        copy_rows(
+            black_box(pixmap.data()),
+            row_bytes,
+            black_box(&mut surface),
+            row_bytes,
+        )
+    });
+    measure("row copy, padded pitch", || {
+        copy_rows(
+            black_box(pixmap.data()),
+            row_bytes,
+            black_box(&mut padded),
+            padded_pitch,
+        )
+    });
+}
@@ -1,14 +1,10 @@
 use clap::{Parser, Subcommand};
-use rand::prelude::*;
-use sdl2::event::Event;
+use sdl2::controller::Button;
 use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
 use serde::{Deserialize, Serialize};
-use std::{
-    ops::{AddAssign, Div, Mul, Sub},
-    path::PathBuf,
-};
-use strategka_core::{Replay, World};
+use std::path::PathBuf;
+use strategka_core::{Fixed, FixedV2, Replay, Rng, SaveGame, Tick, World};
 use strategka_render::*;
 use thiserror::Error;
 use tiny_skia::*;
@@ -21,114 +17,45 @@ pub enum CircleError {
     CircleDraw,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
-struct V2 {
-    x: f32,
-    y: f32,
-}
-
-impl AddAssign for V2 {
-    fn add_assign(&mut self, rhs: Self) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-    }
-}
-
-impl Sub for V2 {
-    type Output = V2;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        V2 {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        }
-    }
-}
-
-impl Mul<f32> for V2 {
-    type Output = V2;
-
-    fn mul(self, rhs: f32) -> Self::Output {
-        V2 {
-            x: self.x * rhs,
-            y: self.y * rhs,
-        }
-    }
-}
-
-impl Mul<V2> for f32 {
-    type Output = V2;
-
-    fn mul(self, rhs: V2) -> Self::Output {
-        V2 {
-            x: self * rhs.x,
-            y: self * rhs.y,
-        }
-    }
-}
-
-impl Div<f32> for V2 {
-    type Output = V2;
-
-    fn div(self, rhs: f32) -> Self::Output {
-        V2 {
-            x: self.x / rhs,
-            y: self.y / rhs,
-        }
-    }
-}
-
-impl Div<V2> for f32 {
-    type Output = V2;
-
-    fn div(self, rhs: V2) -> Self::Output {
-        V2 {
-            x: self / rhs.x,
-            y: self / rhs.y,
-        }
-    }
-}
-
-impl V2 {
-    fn square_dist(&self) -> f32 {
-        self.x * self.x + self.y * self.y
-    }
-}
-
 /// Index of circle
 type CircleId = usize;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct Circle {
-    pos: V2,
-    vel: V2,
-    radius: f32,
-    selected: bool,
-    target: Option<V2>,
+    pos: FixedV2,
+    vel: FixedV2,
+    radius: Fixed,
+    target: Option<FixedV2>,
 }
 
 impl Circle {
-    pub fn new(pos: V2, vel: V2) -> Self {
+    pub fn new(pos: FixedV2, vel: FixedV2) -> Self {
         Circle {
             pos,
             vel,
-            radius: 15.0,
-            selected: false,
+            radius: Fixed::from_int(15),
             target: None,
         }
     }
 
-    pub fn rng(rng: &mut StdRng, width: u32, height: u32) -> Self {
-        let x = rng.gen_range(0.0..width as f32);
-        let y = rng.gen_range(0.0..height as f32);
-        let vx = rng.gen_range(0.0..width as f32);
-        let vy = rng.gen_range(0.0..height as f32);
+    pub fn rng(rng: &mut Rng, width: u32, height: u32) -> Self {
+        let width = Fixed::from_int(width as i32);
+        let height = Fixed::from_int(height as i32);
+        let x = rng.range_fixed(Fixed::ZERO..width);
+        let y = rng.range_fixed(Fixed::ZERO..height);
+        let vx = rng.range_fixed(Fixed::ZERO..width);
+        let vy = rng.range_fixed(Fixed::ZERO..height);
 
-        Circle::new(V2 { x, y }, V2 { x: vx, y: vy })
+        Circle::new(FixedV2::new(x, y), FixedV2::new(vx, vy))
     }
 
-    pub fn render(&self, pixmap: &mut Pixmap) -> Result<(), CircleError> {
-        let paint = if self.selected {
+    pub fn render(
+        &self,
+        pixmap: &mut Pixmap,
+        transform: Transform,
+        selected: bool,
+    ) -> Result<(), CircleError> {
+        let paint = if selected {
             make_paint(50, 127, 150, 200)
         } else {
             make_paint(220, 140, 75, 180)
@@ -136,34 +63,34 @@ impl Circle {
 
         let path = {
             let mut pb = PathBuilder::new();
-            pb.push_circle(self.pos.x as f32, self.pos.y as f32, self.radius as f32);
+            pb.push_circle(
+                self.pos.x.to_f32(),
+                self.pos.y.to_f32(),
+                self.radius.to_f32(),
+            );
             pb.finish().ok_or(CircleError::CircleDraw)?
         };
 
-        pixmap.fill_path(
-            &path,
-            &paint,
-            FillRule::Winding,
-            Transform::identity(),
-            None,
-        );
+        pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
         Ok(())
     }
 
-    pub fn step(&mut self, dt: f32, width: u32, height: u32) {
+    pub fn step(&mut self, rng: &mut Rng, dt: Fixed, width: u32, height: u32) {
         self.pos += self.vel * dt;
 
-        if self.pos.x < 0.0 || self.pos.x > width as f32 {
-            self.vel.x *= -1.;
+        // Walls are a bit bumpy, so the bounce speed is random
+        let bounce = Fixed::from_ratio(9, 10)..Fixed::from_ratio(11, 10);
+        if self.pos.x < Fixed::ZERO || self.pos.x > Fixed::from_int(width as i32) {
+            self.vel.x *= -rng.range_fixed(bounce.clone());
         }
-        if self.pos.y < 0.0 || self.pos.y > height as f32 {
-            self.vel.y *= -1.;
+        if self.pos.y < Fixed::ZERO || self.pos.y > Fixed::from_int(height as i32) {
+            self.vel.y *= -rng.range_fixed(bounce);
         }
 
         if let Some(t) = self.target {
-            let mass = 1.0;
-            let k = 1.0;
-            let c = 0.3;
+            let mass = Fixed::ONE;
+            let k = Fixed::ONE;
+            let c = Fixed::from_ratio(3, 10);
             let dv = t - self.pos;
             let fv = k * dv - c * self.vel;
             self.vel += fv / mass;
@@ -180,20 +107,106 @@ fn make_paint<'a>(r: u8, g: u8, b: u8, a: u8) -> Paint<'a> {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 enum CirclesInput {
-    /// Player selects circle with index i
-    Select(CircleId),
-    /// Player orders to move to the point
-    Move(V2),
+    /// Player orders the circle to move to the point
+    Move(CircleId, FixedV2),
     /// Stop simulation
     EndSimulation,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct CirclesWorld {
     width: u32,
     height: u32,
     circles: Vec<Circle>,
+    rng: Rng,
+}
+
+/// Presentation state that is not part of the simulation
+#[derive(Debug)]
+struct CirclesView {
     selected: Option<CircleId>,
+    hud_scale: f32,
+}
+
+impl Default for CirclesView {
+    fn default() -> Self {
+        CirclesView {
+            selected: None,
+            hud_scale: 2.0,
+        }
+    }
+}
+
+impl View<CirclesWorld> for CirclesView {
+    fn debug_lines(&self, world: &CirclesWorld) -> Vec<String> {
+        let selected = self
+            .selected
+            .and_then(|i| world.circles.get(i).map(|c| (i, c)));
+        match selected {
+            Some((i, circle)) => {
+                let target = circle
+                    .target
+                    .map_or("none".to_owned(), |t| format!("{}, {}", t.x, t.y));
+                vec![
+                    format!("Selected: #{i}"),
+                    format!("Velocity: {}, {}", circle.vel.x, circle.vel.y),
+                    format!("Target: {target}"),
+                ]
+            }
+            None => vec!["Selected: none".to_owned()],
+        }
+    }
+
+    fn ui(&mut self, world: &CirclesWorld, ui: &mut UiFrame) -> Vec<CirclesInput> {
+        let rect = |x, y, w, h| Rect::from_xywh(x, y, w, h).expect("valid widget rect");
+        let mut inputs = vec![];
+        ui.panel(rect(10.0, 40.0, 220.0, 290.0));
+        ui.label("Circles", (18, 48));
+        let items: Vec<String> = world
+            .circles
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("#{i} at {:.0}, {:.0}", c.pos.x.to_f32(), c.pos.y.to_f32()))
+            .collect();
+        ui.list(
+            "circles",
+            rect(18.0, 64.0, 204.0, 140.0),
+            &items,
+            &mut self.selected,
+        );
+
+        let center = rect(18.0, 212.0, 204.0, 24.0);
+        if ui.button("Send to center", center) {
+            if let Some(i) = self.selected {
+                let target = FixedV2::from_int(world.width as i32 / 2, world.height as i32 / 2);
+                inputs.push(CirclesInput::Move(i, target));
+            }
+        }
+        ui.tooltip(
+            center,
+            "Order the selected circle\nto move to the field center",
+        );
+
+        ui.label("HUD scale", (18, 246));
+        ui.slider(
+            "hud scale",
+            rect(18.0, 260.0, 204.0, 16.0),
+            &mut self.hud_scale,
+            1.0..=4.0,
+        );
+        inputs
+    }
+
+    fn loaded(&mut self, _world: &CirclesWorld) {
+        self.selected = None;
+    }
+
+    fn input_player(&self, input: &CirclesInput) -> Option<usize> {
+        match input {
+            CirclesInput::Move(..) => Some(0),
+            CirclesInput::EndSimulation => None,
+        }
+    }
 }
 
 impl Default for CirclesWorld {
@@ -202,18 +215,14 @@ impl Default for CirclesWorld {
             width: 0,
             height: 0,
             circles: vec![],
-            selected: None,
+            rng: Rng::default(),
         }
     }
 }
 
 impl CirclesWorld {
     pub fn new(width: u32, height: u32, circles_num: usize, seed: u64) -> Self {
-        let mut rng = {
-            let mut streched_seed = [0u8; 32];
-            streched_seed[0..8].copy_from_slice(&seed.to_ne_bytes());
-            StdRng::from_seed(streched_seed)
-        };
+        let mut rng = Rng::new(seed);
 
         let mut circles = vec![];
         for _ in 0..circles_num {
@@ -224,48 +233,71 @@ impl CirclesWorld {
             width,
             height,
             circles,
-            selected: None,
+            rng,
         }
     }
 
-    pub fn render(&self) -> Result<Pixmap, CircleError> {
-        let mut pixmap = Pixmap::new(self.width, self.height).ok_or(CircleError::CanvasCreation)?;
+    pub fn render(
+        &self,
+        view: &CirclesView,
+        text: &TextRenderer,
+        ctx: &RenderContext,
+    ) -> Result<Pixmap, CircleError> {
+        let mut pixmap = Pixmap::new(ctx.width, ctx.height).ok_or(CircleError::CanvasCreation)?;
         pixmap.fill(Color::from_rgba8(0, 0, 0, 255));
-        for circle in self.circles.iter() {
-            circle.render(&mut pixmap)?;
+        let transform = ctx.camera.transform();
+        let field = Rect::from_xywh(0.0, 0.0, self.width as f32, self.height as f32)
+            .ok_or(CircleError::CanvasCreation)?;
+        pixmap.fill_rect(field, &make_paint(20, 20, 30, 255), transform, None);
+        for (i, circle) in self.circles.iter().enumerate() {
+            circle.render(&mut pixmap, transform, view.selected == Some(i))?;
         }
+        self.render_hud(&mut pixmap, view, text);
         Ok(pixmap)
     }
 
+    fn render_hud(&self, pixmap: &mut Pixmap, view: &CirclesView, text: &TextRenderer) {
+        let style = TextStyle::new(Color::from_rgba8(220, 220, 220, 255))
+            .scale(view.hud_scale.round() as u32);
+        text.draw(
+            pixmap,
+            &format!("Circles: {}", self.circles.len()),
+            (10, 10),
+            &style,
+        );
+        if let Some(circle) = view.selected.and_then(|i| self.circles.get(i)) {
+            let info = format!(
+                "Selected at {:.0}, {:.0}",
+                circle.pos.x.to_f32(),
+                circle.pos.y.to_f32()
+            );
+            let x = pixmap.width() as i32 - 10;
+            text.draw(pixmap, &info, (x, 10), &style.align(Align::Right));
+        }
+    }
+
     pub fn process_input(&mut self, input: &CirclesInput) {
         match input {
-            CirclesInput::Select(i) => {
-                self.circles[*i].selected = true;
-                if let Some(other) = self.selected {
-                    self.circles[other].selected = false;
-                }
-                self.selected = Some(*i);
-            }
-            CirclesInput::Move(p) => {
-                if let Some(i) = self.selected {
-                    self.circles[i].target = Some(*p);
+            CirclesInput::Move(i, p) => {
+                if let Some(circle) = self.circles.get_mut(*i) {
+                    circle.target = Some(*p);
                 }
             }
             CirclesInput::EndSimulation => {}
         }
     }
 
-    pub fn step(&mut self, dt: f32) {
+    pub fn step(&mut self, tick: Tick) {
         for circle in self.circles.iter_mut() {
-            circle.step(dt, self.width, self.height);
+            circle.step(&mut self.rng, tick.dt(), self.width, self.height);
         }
     }
 
     /// Return first circle under the point
-    pub fn circle_at(&self, pos: V2) -> Option<CircleId> {
+    pub fn circle_at(&self, pos: FixedV2) -> Option<CircleId> {
         self.circles
             .iter()
-            .position(|c| (c.pos - pos).square_dist() < (c.radius * c.radius))
+            .position(|c| c.pos.square_distance(pos) < (c.radius * c.radius))
     }
 }
 
@@ -277,32 +309,128 @@ impl World for CirclesWorld {
     }
 
     fn current_version() -> u32 {
-        1
+        4
     }
 }
 
+const SELECT_ACTION: &str = "select";
+const MOVE_ACTION: &str = "move";
+const REBIND_MOVE_ACTION: &str = "rebind_move";
+const PAUSE_ACTION: &str = "pause";
+const RESTART_ACTION: &str = "restart";
+const TAKE_CONTROL_ACTION: &str = "take_control";
+
+fn circles_bindings() -> Bindings {
+    Bindings::default()
+        .with(QUIT_ACTION, Binding::button(Button::Back))
+        .with(SELECT_ACTION, Binding::mouse(MouseButton::Left))
+        .with(MOVE_ACTION, Binding::mouse(MouseButton::Right))
+        .with(REBIND_MOVE_ACTION, Binding::key(Keycode::F2))
+        .with(PAUSE_ACTION, Binding::key(Keycode::Space))
+        .with(PAUSE_ACTION, Binding::button(Button::Start))
+        .with(RESTART_ACTION, Binding::key(Keycode::R))
+        .with(TAKE_CONTROL_ACTION, Binding::key(Keycode::T))
+        .with(TAKE_CONTROL_ACTION, Binding::button(Button::Y))
+}
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
+    /// Load key bindings from the RON file
+    #[arg(long, global = true)]
+    bindings: Option<PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Play the game and save replay
+    /// Play the game and save replay. Press F2 and then a key or mouse button to rebind
+    /// move order.
     Play {
         /// Where to save replay
         #[arg(short, long)]
         replay: Option<PathBuf>,
+        /// Delay in turns before input is applied
+        #[arg(short, long, default_value_t = 0)]
+        input_delay: u64,
+        /// Where to store quick save (F5 to save, F9 to load)
+        #[arg(short, long)]
+        save: Option<PathBuf>,
+        /// Continue the game from the quick save
+        #[arg(long, requires = "save")]
+        resume: bool,
     },
-    /// Load replay and show it contents
+    /// Load replay and show it contents. Press T to take control at the current turn.
     Replay {
         /// Where replay to load is located
         #[arg(short, long)]
         replay: PathBuf,
+        /// Where to save replay of the game after taking control
+        #[arg(short, long)]
+        branch: Option<PathBuf>,
+    },
+    /// Convert replay between binary and text formats. Files with `.ron`
+    /// extension are treated as text.
+    Convert {
+        /// Replay to convert
+        input: PathBuf,
+        /// Where to write converted replay
+        output: PathBuf,
+    },
+    /// Write down default key bindings to customize them
+    Bindings {
+        /// Where to write the bindings
+        output: PathBuf,
     },
+    /// Compare two replays and find turn at which simulations diverge
+    Diff {
+        /// First replay to compare
+        first: PathBuf,
+        /// Second replay to compare
+        second: PathBuf,
+    },
+}
+
+/// Position of the action in world coordinates
+fn world_position(action: &ActionEvent, ctx: &EventContext) -> FixedV2 {
+    let (x, y) = ctx
+        .camera
+        .screen_to_world((action.position.0 as f32, action.position.1 as f32));
+    FixedV2::from_int(x as i32, y as i32)
+}
+
+/// Change selection in the view, shared by play and replay modes
+fn select_circle(world: &CirclesWorld, view: &mut CirclesView, pos: FixedV2) {
+    view.selected = world.circle_at(pos);
+}
+
+fn play_event_handler(
+    world: &CirclesWorld,
+    view: &mut CirclesView,
+    action: &ActionEvent,
+    ctx: &mut EventContext,
+) -> Result<Vec<CirclesInput>, CircleError> {
+    if action.kind != ActionKind::Pressed {
+        return Ok(vec![]);
+    }
+    match action.action.as_str() {
+        QUIT_ACTION => Ok(vec![CirclesInput::EndSimulation]),
+        SELECT_ACTION => {
+            select_circle(world, view, world_position(action, ctx));
+            Ok(vec![])
+        }
+        MOVE_ACTION => match view.selected {
+            Some(i) => Ok(vec![CirclesInput::Move(i, world_position(action, ctx))]),
+            None => Ok(vec![]),
+        },
+        REBIND_MOVE_ACTION => {
+            ctx.mapper.capture(MOVE_ACTION);
+            Ok(vec![])
+        }
+        _ => Ok(vec![]),
+    }
 }
 
 pub fn main() -> Result<(), Error<CircleError>> {
@@ -316,85 +444,132 @@ pub fn main() -> Result<(), Error<CircleError>> {
         // save_replay: Some("circles.replay".into()),
         ..RenderInfo::default()
     };
+    // Mouse wheel zooms, middle button drags and window edges scroll the field
+    render_info.camera = Camera {
+        min_zoom: 0.5,
+        max_zoom: 4.0,
+        ..Camera::with_bounds(
+            Rect::from_xywh(
+                0.0,
+                0.0,
+                render_info.width as f32,
+                render_info.height as f32,
+            )
+            .expect("field size is positive"),
+        )
+    };
+    render_info.camera_controls = Some(CameraControls::default());
+    render_info.bindings = match args.bindings {
+        Some(path) => Bindings::load(path)?,
+        None => circles_bindings(),
+    };
     let input_handler = |world: &mut CirclesWorld, input: &CirclesInput| {
         world.process_input(input);
         Ok(matches!(input, CirclesInput::EndSimulation))
     };
-    let simulate = |world: &mut CirclesWorld, dt| {
-        world.step(dt / 1_000_000_000.0);
+    let simulate = |world: &mut CirclesWorld, tick| {
+        world.step(tick);
         Ok(())
     };
-    let render_handler = |world: &CirclesWorld| world.render();
+    let text = TextRenderer::default();
+    let render_handler = |world: &CirclesWorld, view: &CirclesView, ctx: &RenderContext| {
+        world.render(view, &text, ctx)
+    };
+    let mut view = CirclesView::default();
     match args.command {
-        Commands::Play { replay } => {
+        Commands::Play {
+            replay,
+            input_delay,
+            save,
+            resume,
+        } => {
             render_info.save_replay = replay;
-            let world = CirclesWorld::new(render_info.width, render_info.height, 20, 42);
-            render_loop(
+            render_info.input_delay = input_delay;
+            render_info.save_game = save.clone();
+            let save_game = match save {
+                Some(path) if resume => SaveGame::load(path)?,
+                _ => {
+                    let world = CirclesWorld::new(render_info.width, render_info.height, 20, 42);
+                    SaveGame::new(&world, render_info.fps)
+                }
+            };
+            resume_loop(
                 &render_info,
-                world,
-                |world, event| match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape),
-                        ..
-                    } => Ok(vec![CirclesInput::EndSimulation]),
-                    Event::MouseButtonDown {
-                        mouse_btn: MouseButton::Left,
-                        x,
-                        y,
-                        ..
-                    } => {
-                        if let Some(i) = world.circle_at(V2 {
-                            x: x as f32,
-                            y: y as f32,
-                        }) {
-                            Ok(vec![CirclesInput::Select(i)])
-                        } else {
-                            Ok(vec![])
-                        }
-                    }
-                    Event::MouseButtonDown {
-                        mouse_btn: MouseButton::Right,
-                        x,
-                        y,
-                        ..
-                    } => Ok(vec![CirclesInput::Move(V2 {
-                        x: x as f32,
-                        y: y as f32,
-                    })]),
-                    _ => Ok(vec![]),
-                },
+                save_game,
+                &mut view,
+                play_event_handler,
                 input_handler,
                 simulate,
                 render_handler,
             )
         }
-        Commands::Replay { replay } => {
+        Commands::Replay { replay, branch } => {
             let loaded_replay = Replay::<CirclesWorld>::load(replay)?;
 
-            replay_loop(
+            let taken = replay_loop(
                 &render_info,
                 &loaded_replay,
-                |_, event| match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape),
-                        ..
-                    } => Ok(Some(ReplayControl::EndReplay)),
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Space),
-                        ..
-                    } => Ok(Some(ReplayControl::ToggleSimulation)),
-                    Event::KeyDown {
-                        keycode: Some(Keycode::R),
-                        ..
-                    } => Ok(Some(ReplayControl::RestartSimulation)),
+                &mut view,
+                |world, view, action, ctx| match action.action.as_str() {
+                    _ if action.kind != ActionKind::Pressed => Ok(None),
+                    QUIT_ACTION => Ok(Some(ReplayControl::EndReplay)),
+                    SELECT_ACTION => {
+                        select_circle(world, view, world_position(action, ctx));
+                        Ok(None)
+                    }
+                    PAUSE_ACTION => Ok(Some(ReplayControl::ToggleSimulation)),
+                    RESTART_ACTION => Ok(Some(ReplayControl::RestartSimulation)),
+                    TAKE_CONTROL_ACTION => Ok(Some(ReplayControl::TakeControl)),
                     _ => Ok(None),
                 },
                 input_handler,
                 simulate,
                 render_handler,
-            )
+            )?;
+            if let Some(save_game) = taken {
+                render_info.save_replay = branch;
+                resume_loop(
+                    &render_info,
+                    save_game,
+                    &mut view,
+                    play_event_handler,
+                    input_handler,
+                    simulate,
+                    render_handler,
+                )?;
+            }
+            Ok(())
+        }
+        Commands::Convert { input, output } => {
+            let is_text = |path: &PathBuf| path.extension().is_some_and(|ext| ext == "ron");
+            let replay = if is_text(&input) {
+                Replay::<CirclesWorld>::load_text(input)?
+            } else {
+                Replay::<CirclesWorld>::load(input)?
+            };
+            if is_text(&output) {
+                replay.save_text(output).map_err(|e| e.into_owned())?;
+            } else {
+                replay.save(output).map_err(|e| e.into_owned())?;
+            }
+            Ok(())
+        }
+        Commands::Bindings { output } => {
+            circles_bindings().save(output)?;
+            Ok(())
+        }
+        Commands::Diff { first, second } => {
+            let first = Replay::<CirclesWorld>::load(first)?;
+            let second = Replay::<CirclesWorld>::load(second)?;
+            print!("{}", first.diff(&second));
+            match first
+                .diverging_turn(&second, input_handler, simulate)
+                .map_err(Error::Simulation)?
+            {
+                Some(turn) => println!("simulations diverge at turn {turn}"),
+                None => println!("simulations are equal"),
+            }
+            Ok(())
         }
     }
 }
@@ -1,7 +1,5 @@
 use std::convert::Infallible;
 
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
 use serde::{Deserialize, Serialize};
 use strategka_core::World;
 use strategka_render::*;
@@ -107,21 +105,21 @@ pub fn main() -> Result<(), Error<Infallible>> {
     render_loop(
         &render_info,
         TriangleWorld::default(),
-        |_, event| match event {
-            Event::Quit { .. }
-            | Event::KeyDown {
-                keycode: Some(Keycode::Escape),
-                ..
-            } => Ok(vec![TriangleInput::EndSimulation]),
-            _ => Ok(vec![]),
+        &mut (),
+        |_, _, action, _| {
+            if action.pressed(QUIT_ACTION) {
+                Ok(vec![TriangleInput::EndSimulation])
+            } else {
+                Ok(vec![])
+            }
         },
         |_, input| match input {
             TriangleInput::EndSimulation => Ok(true),
         },
-        |w, dt| {
-            w.i += dt / 1_000_000_000.0;
+        |w, tick| {
+            w.i += tick.dt_f32();
             Ok(())
         },
-        |w| Ok(render(render_info.width, render_info.height, w.i)),
+        |w, _, ctx| Ok(render(ctx.width, ctx.height, w.i)),
     )
 }
@@ -0,0 +1,864 @@
+use sdl2::controller::{Axis, Button};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// Action that is emitted when the window is closed. Escape is bound to it by default.
+pub const QUIT_ACTION: &str = "quit";
+/// Action that writes down the quick save. F5 is bound to it by default.
+pub const QUICKSAVE_ACTION: &str = "quicksave";
+/// Action that loads the quick save. F9 is bound to it by default.
+pub const QUICKLOAD_ACTION: &str = "quickload";
+/// Action that toggles fullscreen mode. F11 is bound to it by default.
+pub const FULLSCREEN_ACTION: &str = "fullscreen";
+/// Action that toggles the debug overlay. F3 is bound to it by default.
+pub const DEBUG_ACTION: &str = "debug";
+
+#[derive(Debug, Error)]
+pub enum BindingsError {
+    #[error("Failed to access bindings file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse bindings: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Failed to serialize bindings: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+/// Physical key or button that can be bound to an action.
+///
+/// Serialized by SDL names, e.g. `Key("Left Ctrl")`, `Mouse("Right")`, `Button("a")`
+/// or `Axis("leftx", Negative)`.
+///
+/// Gamepad triggers are not tied to a gamepad: they are held while any connected gamepad
+/// holds them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "TriggerName", into = "TriggerName")]
+pub enum Trigger {
+    Key(Keycode),
+    Mouse(MouseButton),
+    /// Gamepad button
+    Button(Button),
+    /// Gamepad stick or trigger deflected to the side further than the deadzone
+    Axis(Axis, AxisSide),
+}
+
+/// Direction of gamepad axis deflection. Triggers only have positive side.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AxisSide {
+    Negative,
+    Positive,
+}
+
+impl Trigger {
+    fn is_modifier(&self) -> bool {
+        matches!(
+            self,
+            Trigger::Key(
+                Keycode::LCtrl
+                    | Keycode::RCtrl
+                    | Keycode::LShift
+                    | Keycode::RShift
+                    | Keycode::LAlt
+                    | Keycode::RAlt
+            )
+        )
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trigger::Key(key) => write!(f, "{}", key.name()),
+            Trigger::Mouse(button) => write!(f, "Mouse {}", mouse_button_name(*button)),
+            Trigger::Button(button) => write!(f, "Gamepad {}", button.string()),
+            Trigger::Axis(axis, AxisSide::Negative) => write!(f, "Gamepad {}-", axis.string()),
+            Trigger::Axis(axis, AxisSide::Positive) => write!(f, "Gamepad {}+", axis.string()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum TriggerName {
+    Key(String),
+    Mouse(String),
+    Button(String),
+    Axis(String, AxisSide),
+}
+
+impl From<Trigger> for TriggerName {
+    fn from(trigger: Trigger) -> Self {
+        match trigger {
+            Trigger::Key(key) => TriggerName::Key(key.name()),
+            Trigger::Mouse(button) => TriggerName::Mouse(mouse_button_name(button).to_owned()),
+            Trigger::Button(button) => TriggerName::Button(button.string()),
+            Trigger::Axis(axis, side) => TriggerName::Axis(axis.string(), side),
+        }
+    }
+}
+
+impl TryFrom<TriggerName> for Trigger {
+    type Error = String;
+
+    fn try_from(name: TriggerName) -> Result<Self, Self::Error> {
+        match name {
+            TriggerName::Key(name) => Keycode::from_name(&name)
+                .map(Trigger::Key)
+                .ok_or_else(|| format!("unknown key '{name}'")),
+            TriggerName::Mouse(name) => mouse_button_from_name(&name)
+                .map(Trigger::Mouse)
+                .ok_or_else(|| format!("unknown mouse button '{name}'")),
+            TriggerName::Button(name) => Button::from_string(&name)
+                .map(Trigger::Button)
+                .ok_or_else(|| format!("unknown gamepad button '{name}'")),
+            TriggerName::Axis(name, side) => Axis::from_string(&name)
+                .map(|axis| Trigger::Axis(axis, side))
+                .ok_or_else(|| format!("unknown gamepad axis '{name}'")),
+        }
+    }
+}
+
+fn mouse_button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "Left",
+        MouseButton::Middle => "Middle",
+        MouseButton::Right => "Right",
+        MouseButton::X1 => "X1",
+        MouseButton::X2 => "X2",
+        MouseButton::Unknown => "Unknown",
+    }
+}
+
+fn mouse_button_from_name(name: &str) -> Option<MouseButton> {
+    match name {
+        "Left" => Some(MouseButton::Left),
+        "Middle" => Some(MouseButton::Middle),
+        "Right" => Some(MouseButton::Right),
+        "X1" => Some(MouseButton::X1),
+        "X2" => Some(MouseButton::X2),
+        _ => None,
+    }
+}
+
+/// Modifier keys that must be held for a binding. Left and right keys are not distinguished.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mods {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Mods {
+    fn held<'a, T: IntoIterator<Item = &'a Trigger>>(pressed: T) -> Self {
+        let mut mods = Mods::default();
+        for trigger in pressed {
+            match trigger {
+                Trigger::Key(Keycode::LCtrl | Keycode::RCtrl) => mods.ctrl = true,
+                Trigger::Key(Keycode::LShift | Keycode::RShift) => mods.shift = true,
+                Trigger::Key(Keycode::LAlt | Keycode::RAlt) => mods.alt = true,
+                _ => (),
+            }
+        }
+        mods
+    }
+}
+
+/// Combination of triggers that activates an action. All triggers of the chord must be
+/// held, the action is pressed when the last of them goes down and released when any
+/// of them goes up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binding {
+    pub triggers: Vec<Trigger>,
+    #[serde(default)]
+    pub mods: Mods,
+}
+
+impl Binding {
+    pub fn key(key: Keycode) -> Self {
+        Binding {
+            triggers: vec![Trigger::Key(key)],
+            mods: Mods::default(),
+        }
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Binding {
+            triggers: vec![Trigger::Mouse(button)],
+            mods: Mods::default(),
+        }
+    }
+
+    pub fn button(button: Button) -> Self {
+        Binding {
+            triggers: vec![Trigger::Button(button)],
+            mods: Mods::default(),
+        }
+    }
+
+    pub fn axis(axis: Axis, side: AxisSide) -> Self {
+        Binding {
+            triggers: vec![Trigger::Axis(axis, side)],
+            mods: Mods::default(),
+        }
+    }
+
+    pub fn chord(triggers: Vec<Trigger>) -> Self {
+        Binding {
+            triggers,
+            mods: Mods::default(),
+        }
+    }
+
+    pub fn with_mods(self, mods: Mods) -> Self {
+        Binding { mods, ..self }
+    }
+
+    /// Check that the binding is satisfied by the held triggers
+    fn matches(&self, pressed: &HashSet<Trigger>) -> bool {
+        let mods = Mods::held(pressed.iter().filter(|t| !self.triggers.contains(t)));
+        mods == self.mods && self.triggers.iter().all(|t| pressed.contains(t))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, name) in [
+            (self.mods.ctrl, "Ctrl"),
+            (self.mods.shift, "Shift"),
+            (self.mods.alt, "Alt"),
+        ] {
+            if flag {
+                write!(f, "{name}+")?;
+            }
+        }
+        for (i, trigger) in self.triggers.iter().enumerate() {
+            if i > 0 {
+                write!(f, "+")?;
+            }
+            write!(f, "{trigger}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Named actions and their bindings. Stored as RON map:
+///
+/// ```ron
+/// {
+///     "quit": [(triggers: [Key("Escape")])],
+///     "select": [(triggers: [Mouse("Left")])],
+///     "select_all": [(triggers: [Key("A")], mods: (ctrl: true))],
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Bindings {
+    actions: BTreeMap<String, Vec<Binding>>,
+}
+
+impl Bindings {
+    /// Bindings without any action
+    pub fn empty() -> Self {
+        Bindings {
+            actions: BTreeMap::new(),
+        }
+    }
+
+    /// Add binding to the action keeping the existing ones
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        self.actions
+            .entry(action.to_owned())
+            .or_default()
+            .push(binding);
+    }
+
+    /// The same as [`Bindings::bind`], but in builder style
+    pub fn with(mut self, action: &str, binding: Binding) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    /// Replace all bindings of the action with the given one
+    pub fn rebind(&mut self, action: &str, binding: Binding) {
+        self.actions.insert(action.to_owned(), vec![binding]);
+    }
+
+    /// Remove all bindings of the action
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    /// Bindings of the action
+    pub fn get(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Iterate over all actions and their bindings
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Binding)> {
+        self.actions.iter().flat_map(|(action, bindings)| {
+            bindings
+                .iter()
+                .map(move |binding| (action.as_str(), binding))
+        })
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, BindingsError> {
+        Ok(ron::from_str(text)?)
+    }
+
+    pub fn to_ron(&self) -> Result<String, BindingsError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::new(),
+        )?)
+    }
+
+    /// Load bindings from RON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BindingsError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    /// Write down bindings into RON file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BindingsError> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+}
+
+impl Default for Bindings {
+    /// Quit on Escape, debug overlay on F3, quick save on F5, quick load on F9 and
+    /// fullscreen on F11
+    fn default() -> Self {
+        Bindings::empty()
+            .with(QUIT_ACTION, Binding::key(Keycode::Escape))
+            .with(DEBUG_ACTION, Binding::key(Keycode::F3))
+            .with(QUICKSAVE_ACTION, Binding::key(Keycode::F5))
+            .with(QUICKLOAD_ACTION, Binding::key(Keycode::F9))
+            .with(FULLSCREEN_ACTION, Binding::key(Keycode::F11))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ActionKind {
+    Pressed,
+    /// Value of the action bound to gamepad axis is changed while the action is held
+    Changed,
+    Released,
+}
+
+/// Action resolved from raw events
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionEvent {
+    pub action: String,
+    pub kind: ActionKind,
+    /// Position of the mouse cursor in window coordinates. The render loops convert it
+    /// into pixmap coordinates of the last presented frame.
+    pub position: (i32, i32),
+    /// Strength of the action in range `0.0..=1.0`. Keys and buttons give `1.0` when
+    /// pressed, gamepad axes give deflection rescaled from the deadzone to the edge.
+    pub value: f32,
+}
+
+impl ActionEvent {
+    /// Check that the action has just been pressed
+    pub fn pressed(&self, action: &str) -> bool {
+        self.kind == ActionKind::Pressed && self.action == action
+    }
+
+    /// Check that the action has just been released
+    pub fn released(&self, action: &str) -> bool {
+        self.kind == ActionKind::Released && self.action == action
+    }
+}
+
+/// Default deadzone of gamepad axes
+pub const DEFAULT_DEADZONE: f32 = 0.25;
+
+/// Tracks held keys and buttons and turns SDL events into [`ActionEvent`]s
+#[derive(Debug, Clone)]
+pub struct ActionMapper {
+    pub bindings: Bindings,
+    /// Fraction of gamepad axis range near the center that is ignored
+    pub deadzone: f32,
+    pressed: HashSet<Trigger>,
+    /// Gamepad triggers held by each gamepad, keyed by joystick instance id
+    gamepads: HashMap<u32, HashSet<Trigger>>,
+    active: Vec<(String, Binding)>,
+    capture: Option<String>,
+    position: (i32, i32),
+}
+
+impl ActionMapper {
+    pub fn new(bindings: Bindings) -> Self {
+        ActionMapper {
+            bindings,
+            deadzone: DEFAULT_DEADZONE,
+            pressed: HashSet::new(),
+            gamepads: HashMap::new(),
+            active: vec![],
+            capture: None,
+            position: (0, 0),
+        }
+    }
+
+    /// Bind the next pressed key or button (with held modifiers) to the action, replacing
+    /// its bindings. The press is consumed and doesn't produce any action.
+    pub fn capture(&mut self, action: &str) {
+        self.capture = Some(action.to_owned());
+    }
+
+    /// Action that waits for a new binding
+    pub fn capturing(&self) -> Option<&str> {
+        self.capture.as_deref()
+    }
+
+    /// Last known position of the mouse cursor in window coordinates
+    pub fn position(&self) -> (i32, i32) {
+        self.position
+    }
+
+    /// Check that the trigger is held down
+    pub fn is_held(&self, trigger: Trigger) -> bool {
+        self.pressed.contains(&trigger)
+    }
+
+    /// Resolve actions from the raw event. Window close always gives [`QUIT_ACTION`].
+    pub fn map_event(&mut self, event: &Event) -> Vec<ActionEvent> {
+        match *event {
+            Event::Quit { .. } => vec![self.action_event(QUIT_ACTION, ActionKind::Pressed, 1.0)],
+            Event::KeyDown {
+                keycode: Some(key),
+                repeat: false,
+                ..
+            } => self.press(Trigger::Key(key), 1.0),
+            Event::KeyUp {
+                keycode: Some(key), ..
+            } => self.release(Trigger::Key(key)),
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => {
+                self.position = (x, y);
+                self.press(Trigger::Mouse(mouse_btn), 1.0)
+            }
+            Event::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => {
+                self.position = (x, y);
+                self.release(Trigger::Mouse(mouse_btn))
+            }
+            Event::MouseMotion { x, y, .. } => {
+                self.position = (x, y);
+                vec![]
+            }
+            Event::ControllerButtonDown { which, button, .. } => {
+                self.press_gamepad(which, Trigger::Button(button), 1.0)
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                self.release_gamepad(which, Trigger::Button(button))
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                let value = (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+                let negative = Trigger::Axis(axis, AxisSide::Negative);
+                let mut events = self.move_axis(which, negative, -value);
+                let positive = Trigger::Axis(axis, AxisSide::Positive);
+                events.extend(self.move_axis(which, positive, value));
+                events
+            }
+            // Held buttons of the unplugged gamepad would never be released
+            Event::ControllerDeviceRemoved { which, .. } => {
+                let held = self.gamepads.remove(&which).unwrap_or_default();
+                let released: Vec<Trigger> = held
+                    .into_iter()
+                    .filter(|t| !self.held_by_gamepad(*t))
+                    .collect();
+                released.into_iter().flat_map(|t| self.release(t)).collect()
+            }
+            // Key up events are not delivered to unfocused window
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => self.release_all(),
+            _ => vec![],
+        }
+    }
+
+    fn action_event(&self, action: &str, kind: ActionKind, value: f32) -> ActionEvent {
+        ActionEvent {
+            action: action.to_owned(),
+            kind,
+            position: self.position,
+            value,
+        }
+    }
+
+    /// Press, update or release axis trigger of the gamepad according to deflection in
+    /// `-1.0..=1.0`
+    fn move_axis(&mut self, which: u32, trigger: Trigger, deflection: f32) -> Vec<ActionEvent> {
+        let deadzone = self.deadzone.clamp(0.0, 0.99);
+        if deflection <= deadzone {
+            return self.release_gamepad(which, trigger);
+        }
+        let value = ((deflection - deadzone) / (1.0 - deadzone)).min(1.0);
+        if !self.pressed.contains(&trigger) {
+            return self.press_gamepad(which, trigger, value);
+        }
+        self.gamepads.entry(which).or_default().insert(trigger);
+        self.active
+            .iter()
+            .filter(|(_, b)| b.triggers.contains(&trigger))
+            .map(|(action, _)| self.action_event(action, ActionKind::Changed, value))
+            .collect()
+    }
+
+    fn press(&mut self, trigger: Trigger, value: f32) -> Vec<ActionEvent> {
+        if !self.pressed.insert(trigger) {
+            return vec![];
+        }
+        if let Some(action) = self.capture.take() {
+            if trigger.is_modifier() {
+                self.capture = Some(action);
+            } else {
+                let mods = Mods::held(self.pressed.iter().filter(|t| **t != trigger));
+                let binding = Binding::chord(vec![trigger]).with_mods(mods);
+                self.bindings.rebind(&action, binding);
+            }
+            return vec![];
+        }
+        let matching: Vec<(&str, &Binding)> = self
+            .bindings
+            .iter()
+            .filter(|(_, b)| b.triggers.contains(&trigger) && b.matches(&self.pressed))
+            .collect();
+        // Chords take precedence over their parts
+        let longest = matching
+            .iter()
+            .map(|(_, b)| b.triggers.len())
+            .max()
+            .unwrap_or(0);
+        let fired: Vec<(String, Binding)> = matching
+            .into_iter()
+            .filter(|(_, b)| b.triggers.len() == longest)
+            .map(|(action, b)| (action.to_owned(), b.clone()))
+            .collect();
+        let events = fired
+            .iter()
+            .map(|(action, _)| self.action_event(action, ActionKind::Pressed, value))
+            .collect();
+        self.active.extend(fired);
+        events
+    }
+
+    /// Hold the trigger on the gamepad, actions are pressed only by the first gamepad
+    fn press_gamepad(&mut self, which: u32, trigger: Trigger, value: f32) -> Vec<ActionEvent> {
+        self.gamepads.entry(which).or_default().insert(trigger);
+        self.press(trigger, value)
+    }
+
+    /// Let the trigger go on the gamepad, actions are released when no gamepad holds it
+    fn release_gamepad(&mut self, which: u32, trigger: Trigger) -> Vec<ActionEvent> {
+        let held = self
+            .gamepads
+            .get_mut(&which)
+            .is_some_and(|held| held.remove(&trigger));
+        if held && !self.held_by_gamepad(trigger) {
+            self.release(trigger)
+        } else {
+            vec![]
+        }
+    }
+
+    fn held_by_gamepad(&self, trigger: Trigger) -> bool {
+        self.gamepads.values().any(|held| held.contains(&trigger))
+    }
+
+    fn release(&mut self, trigger: Trigger) -> Vec<ActionEvent> {
+        if !self.pressed.remove(&trigger) {
+            return vec![];
+        }
+        let (released, active): (Vec<_>, Vec<_>) = std::mem::take(&mut self.active)
+            .into_iter()
+            .partition(|(_, b)| b.triggers.contains(&trigger));
+        self.active = active;
+        released
+            .iter()
+            .map(|(action, _)| self.action_event(action, ActionKind::Released, 0.0))
+            .collect()
+    }
+
+    fn release_all(&mut self) -> Vec<ActionEvent> {
+        self.pressed.clear();
+        self.gamepads.clear();
+        std::mem::take(&mut self.active)
+            .iter()
+            .map(|(action, _)| self.action_event(action, ActionKind::Released, 0.0))
+            .collect()
+    }
+}
+
+impl Default for ActionMapper {
+    fn default() -> Self {
+        ActionMapper::new(Bindings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::keyboard::Mod;
+
+    fn key_down(key: Keycode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(key),
+            scancode: None,
+            keymod: Mod::empty(),
+            repeat: false,
+        }
+    }
+
+    fn key_up(key: Keycode) -> Event {
+        Event::KeyUp {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(key),
+            scancode: None,
+            keymod: Mod::empty(),
+            repeat: false,
+        }
+    }
+
+    fn axis_motion(axis: Axis, value: i16) -> Event {
+        Event::ControllerAxisMotion {
+            timestamp: 0,
+            which: 0,
+            axis,
+            value,
+        }
+    }
+
+    fn kinds(events: Vec<ActionEvent>) -> Vec<(String, ActionKind)> {
+        events.into_iter().map(|e| (e.action, e.kind)).collect()
+    }
+
+    fn event(action: &str, kind: ActionKind) -> Vec<(String, ActionKind)> {
+        vec![(action.to_owned(), kind)]
+    }
+
+    const CTRL: Mods = Mods {
+        ctrl: true,
+        shift: false,
+        alt: false,
+    };
+
+    #[test]
+    fn chords_and_modifiers() {
+        let bindings = Bindings::empty()
+            .with("copy", Binding::key(Keycode::C).with_mods(CTRL))
+            .with("center", Binding::key(Keycode::C))
+            .with("left", Binding::key(Keycode::A))
+            .with(
+                "dash",
+                Binding::chord(vec![Trigger::Key(Keycode::A), Trigger::Key(Keycode::S)]),
+            );
+        let mut mapper = ActionMapper::new(bindings);
+        let mut map = |e| kinds(mapper.map_event(&e));
+
+        assert_eq!(
+            map(key_down(Keycode::C)),
+            event("center", ActionKind::Pressed)
+        );
+        assert_eq!(
+            map(key_up(Keycode::C)),
+            event("center", ActionKind::Released)
+        );
+        // Held modifier selects the binding with the same modifiers only
+        assert_eq!(map(key_down(Keycode::LCtrl)), vec![]);
+        assert_eq!(
+            map(key_down(Keycode::C)),
+            event("copy", ActionKind::Pressed)
+        );
+        assert_eq!(map(key_up(Keycode::C)), event("copy", ActionKind::Released));
+        assert_eq!(map(key_up(Keycode::LCtrl)), vec![]);
+
+        assert_eq!(
+            map(key_down(Keycode::A)),
+            event("left", ActionKind::Pressed)
+        );
+        assert_eq!(
+            map(key_down(Keycode::S)),
+            event("dash", ActionKind::Pressed)
+        );
+        assert_eq!(map(key_up(Keycode::S)), event("dash", ActionKind::Released));
+        assert_eq!(map(key_up(Keycode::A)), event("left", ActionKind::Released));
+    }
+
+    #[test]
+    fn capture_rebinds_action() {
+        let mut mapper = ActionMapper::default();
+        mapper.capture(QUIT_ACTION);
+        assert_eq!(mapper.capturing(), Some(QUIT_ACTION));
+        // Modifiers wait for the key they are held with
+        assert_eq!(mapper.map_event(&key_down(Keycode::LCtrl)), vec![]);
+        assert_eq!(mapper.capturing(), Some(QUIT_ACTION));
+        assert_eq!(mapper.map_event(&key_down(Keycode::Q)), vec![]);
+        assert_eq!(mapper.capturing(), None);
+        assert_eq!(
+            mapper.bindings.get(QUIT_ACTION),
+            &[Binding::key(Keycode::Q).with_mods(CTRL)]
+        );
+
+        assert_eq!(mapper.map_event(&key_up(Keycode::Q)), vec![]);
+        assert_eq!(
+            kinds(mapper.map_event(&key_down(Keycode::Q))),
+            event(QUIT_ACTION, ActionKind::Pressed)
+        );
+        assert_eq!(mapper.map_event(&key_up(Keycode::LCtrl)), vec![]);
+        assert_eq!(mapper.map_event(&key_down(Keycode::Escape)), vec![]);
+    }
+
+    #[test]
+    fn axis_deadzone() {
+        let bindings =
+            Bindings::empty().with("right", Binding::axis(Axis::LeftX, AxisSide::Positive));
+        let mut mapper = ActionMapper::new(bindings);
+        mapper.deadzone = 0.5;
+
+        assert_eq!(
+            mapper.map_event(&axis_motion(Axis::LeftX, i16::MAX / 4)),
+            vec![]
+        );
+        let pressed = mapper.map_event(&axis_motion(Axis::LeftX, i16::MAX / 4 * 3));
+        assert_eq!(pressed.len(), 1);
+        assert_eq!(pressed[0].kind, ActionKind::Pressed);
+        // Deflection is rescaled from the deadzone to the edge
+        assert!((pressed[0].value - 0.5).abs() < 0.01);
+
+        let changed = mapper.map_event(&axis_motion(Axis::LeftX, i16::MAX));
+        assert_eq!(kinds(changed.clone()), event("right", ActionKind::Changed));
+        assert_eq!(changed[0].value, 1.0);
+        assert_eq!(
+            kinds(mapper.map_event(&axis_motion(Axis::LeftX, 0))),
+            event("right", ActionKind::Released)
+        );
+        assert_eq!(
+            mapper.map_event(&axis_motion(Axis::LeftX, i16::MIN)),
+            vec![]
+        );
+        assert!(mapper.is_held(Trigger::Axis(Axis::LeftX, AxisSide::Negative)));
+    }
+
+    #[test]
+    fn release_on_focus_lost_and_device_removal() {
+        let bindings = Bindings::empty()
+            .with("jump", Binding::key(Keycode::Space))
+            .with("fire", Binding::button(Button::A));
+        let mut mapper = ActionMapper::new(bindings);
+        mapper.map_event(&key_down(Keycode::Space));
+        mapper.map_event(&Event::ControllerButtonDown {
+            timestamp: 0,
+            which: 0,
+            button: Button::A,
+        });
+
+        let removed = Event::ControllerDeviceRemoved {
+            timestamp: 0,
+            which: 0,
+        };
+        assert_eq!(
+            kinds(mapper.map_event(&removed)),
+            event("fire", ActionKind::Released)
+        );
+        assert!(!mapper.is_held(Trigger::Button(Button::A)));
+        assert!(mapper.is_held(Trigger::Key(Keycode::Space)));
+
+        let focus_lost = Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event: WindowEvent::FocusLost,
+        };
+        assert_eq!(
+            kinds(mapper.map_event(&focus_lost)),
+            event("jump", ActionKind::Released)
+        );
+        assert!(!mapper.is_held(Trigger::Key(Keycode::Space)));
+        assert_eq!(mapper.map_event(&key_up(Keycode::Space)), vec![]);
+    }
+
+    #[test]
+    fn several_gamepads() {
+        let bindings = Bindings::empty()
+            .with("fire", Binding::button(Button::A))
+            .with("jump", Binding::button(Button::B));
+        let mut mapper = ActionMapper::new(bindings);
+        let button = |which, button, down| {
+            if down {
+                Event::ControllerButtonDown {
+                    timestamp: 0,
+                    which,
+                    button,
+                }
+            } else {
+                Event::ControllerButtonUp {
+                    timestamp: 0,
+                    which,
+                    button,
+                }
+            }
+        };
+        let removed = |which| Event::ControllerDeviceRemoved {
+            timestamp: 0,
+            which,
+        };
+
+        assert_eq!(
+            kinds(mapper.map_event(&button(0, Button::A, true))),
+            event("fire", ActionKind::Pressed)
+        );
+        // The same button of another gamepad doesn't press the action twice
+        assert_eq!(mapper.map_event(&button(1, Button::A, true)), vec![]);
+        assert_eq!(mapper.map_event(&button(0, Button::A, false)), vec![]);
+        assert!(mapper.is_held(Trigger::Button(Button::A)));
+
+        mapper.map_event(&button(0, Button::B, true));
+        // Unplugged gamepad releases only what no other gamepad holds
+        assert_eq!(
+            kinds(mapper.map_event(&removed(0))),
+            event("jump", ActionKind::Released)
+        );
+        assert!(mapper.is_held(Trigger::Button(Button::A)));
+        assert_eq!(
+            kinds(mapper.map_event(&button(1, Button::A, false))),
+            event("fire", ActionKind::Released)
+        );
+        assert_eq!(mapper.map_event(&removed(1)), vec![]);
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let bindings = Bindings::default()
+            .with("copy", Binding::key(Keycode::C).with_mods(CTRL))
+            .with("select", Binding::mouse(MouseButton::Left))
+            .with("fire", Binding::button(Button::A))
+            .with("turn", Binding::axis(Axis::LeftX, AxisSide::Negative))
+            .with(
+                "dash",
+                Binding::chord(vec![Trigger::Key(Keycode::A), Trigger::Key(Keycode::S)]),
+            );
+        let text = bindings.to_ron().expect("serialized");
+        assert_eq!(Bindings::from_ron(&text).expect("parsed"), bindings);
+
+        let parsed =
+            Bindings::from_ron(r#"{"select": [(triggers: [Mouse("Right")])]}"#).expect("parsed");
+        assert_eq!(parsed.get("select"), &[Binding::mouse(MouseButton::Right)]);
+        assert!(Bindings::from_ron(r#"{"select": [(triggers: [Mouse("Nope")])]}"#).is_err());
+    }
+}
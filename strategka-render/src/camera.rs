@@ -0,0 +1,279 @@
+use sdl2::mouse::MouseButton;
+use tiny_skia::{Rect, Transform};
+
+/// View into the world that is maintained by the render loop. It is not part of the
+/// simulation, so panning and zooming are never recorded in replays.
+///
+/// Screen coordinates are pixmap pixels, world coordinates are whatever units the game
+/// draws in. The default camera maps them one to one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera {
+    /// World point at the top left corner of the screen
+    pub offset: (f32, f32),
+    /// Screen pixels per world unit
+    pub zoom: f32,
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// World area that the camera is not allowed to leave
+    pub bounds: Option<Rect>,
+    /// Size of the screen in pixels, updated by the render loop
+    pub screen: (u32, u32),
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            offset: (0.0, 0.0),
+            zoom: 1.0,
+            min_zoom: 0.1,
+            max_zoom: 10.0,
+            bounds: None,
+            screen: (0, 0),
+        }
+    }
+}
+
+impl Camera {
+    /// Camera that keeps the view inside the given world area
+    pub fn with_bounds(bounds: Rect) -> Self {
+        Camera {
+            bounds: Some(bounds),
+            ..Camera::default()
+        }
+    }
+
+    /// Transformation from world to screen coordinates for drawing with `tiny_skia`
+    pub fn transform(&self) -> Transform {
+        Transform::from_row(
+            self.zoom,
+            0.0,
+            0.0,
+            self.zoom,
+            -self.offset.0 * self.zoom,
+            -self.offset.1 * self.zoom,
+        )
+    }
+
+    pub fn world_to_screen(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            (x - self.offset.0) * self.zoom,
+            (y - self.offset.1) * self.zoom,
+        )
+    }
+
+    pub fn screen_to_world(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (x / self.zoom + self.offset.0, y / self.zoom + self.offset.1)
+    }
+
+    /// World area that is visible on the screen
+    pub fn visible(&self) -> Option<Rect> {
+        let (w, h) = self.screen;
+        Rect::from_xywh(
+            self.offset.0,
+            self.offset.1,
+            w as f32 / self.zoom,
+            h as f32 / self.zoom,
+        )
+    }
+
+    /// Move the view by the given amount of screen pixels
+    pub fn pan(&mut self, (dx, dy): (f32, f32)) {
+        self.offset.0 += dx / self.zoom;
+        self.offset.1 += dy / self.zoom;
+        self.clamp();
+    }
+
+    /// Multiply zoom by `factor` keeping the world point under `anchor` screen point in place
+    pub fn zoom_at(&mut self, factor: f32, anchor: (f32, f32)) {
+        let fixed = self.screen_to_world(anchor);
+        self.zoom = (self.zoom * factor).clamp(self.min_zoom, self.max_zoom);
+        self.offset = (
+            fixed.0 - anchor.0 / self.zoom,
+            fixed.1 - anchor.1 / self.zoom,
+        );
+        self.clamp();
+    }
+
+    /// Center the view on the world point
+    pub fn look_at(&mut self, (x, y): (f32, f32)) {
+        let (w, h) = self.screen;
+        self.offset = (
+            x - w as f32 / self.zoom / 2.0,
+            y - h as f32 / self.zoom / 2.0,
+        );
+        self.clamp();
+    }
+
+    /// Keep the view inside bounds. Bounds smaller than the view are centered.
+    pub fn clamp(&mut self) {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let (w, h) = self.screen;
+        let clamp_axis = |offset: f32, min: f32, size: f32, visible: f32| {
+            if visible >= size {
+                min - (visible - size) / 2.0
+            } else {
+                offset.clamp(min, min + size - visible)
+            }
+        };
+        self.offset = (
+            clamp_axis(
+                self.offset.0,
+                bounds.left(),
+                bounds.width(),
+                w as f32 / self.zoom,
+            ),
+            clamp_axis(
+                self.offset.1,
+                bounds.top(),
+                bounds.height(),
+                h as f32 / self.zoom,
+            ),
+        );
+    }
+}
+
+/// How the render loop moves the [`Camera`] in response to the mouse
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CameraControls {
+    /// Distance in pixels from the window edge where the cursor scrolls the view. Zero
+    /// disables edge scrolling.
+    pub edge_margin: u32,
+    /// Speed of edge scrolling in screen pixels per second
+    pub edge_speed: f32,
+    /// Mouse button that drags the view while held
+    pub drag_button: Option<MouseButton>,
+    /// Zoom multiplier of a single mouse wheel step. One disables zooming.
+    pub wheel_zoom: f32,
+}
+
+impl Default for CameraControls {
+    fn default() -> Self {
+        CameraControls {
+            edge_margin: 8,
+            edge_speed: 800.0,
+            drag_button: Some(MouseButton::Middle),
+            wheel_zoom: 1.1,
+        }
+    }
+}
+
+impl CameraControls {
+    /// Direction of edge scrolling for the cursor at screen point
+    pub fn edge_direction(&self, (x, y): (i32, i32), (w, h): (u32, u32)) -> (f32, f32) {
+        let margin = self.edge_margin as i32;
+        if margin == 0 {
+            return (0.0, 0.0);
+        }
+        let axis = |pos: i32, size: u32| {
+            if pos < margin {
+                -1.0
+            } else if pos >= size as i32 - margin {
+                1.0
+            } else {
+                0.0
+            }
+        };
+        (axis(x, w), axis(y, h))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near((ax, ay): (f32, f32), (bx, by): (f32, f32)) {
+        assert!(
+            (ax - bx).abs() < 1e-3 && (ay - by).abs() < 1e-3,
+            "({ax}, {ay}) != ({bx}, {by})"
+        );
+    }
+
+    fn camera() -> Camera {
+        Camera {
+            offset: (100.0, -50.0),
+            zoom: 2.5,
+            screen: (800, 600),
+            ..Camera::default()
+        }
+    }
+
+    #[test]
+    fn screen_world_roundtrip() {
+        let camera = camera();
+        for point in [(0.0, 0.0), (13.5, 700.0), (-40.0, 1e3)] {
+            assert_near(camera.world_to_screen(camera.screen_to_world(point)), point);
+            assert_near(camera.screen_to_world(camera.world_to_screen(point)), point);
+        }
+        assert_near(camera.screen_to_world((0.0, 0.0)), camera.offset);
+        let mut mapped = [tiny_skia::Point::from_xy(110.0, -40.0)];
+        camera.transform().map_points(&mut mapped);
+        assert_near(
+            (mapped[0].x, mapped[0].y),
+            camera.world_to_screen((110.0, -40.0)),
+        );
+    }
+
+    #[test]
+    fn zoom_keeps_anchor() {
+        let mut camera = camera();
+        let anchor = (300.0, 200.0);
+        let fixed = camera.screen_to_world(anchor);
+        camera.zoom_at(1.5, anchor);
+        assert_eq!(camera.zoom, 3.75);
+        assert_near(camera.screen_to_world(anchor), fixed);
+
+        // Zoom is limited, the anchor stays in place anyway
+        camera.zoom_at(100.0, anchor);
+        assert_eq!(camera.zoom, camera.max_zoom);
+        assert_near(camera.screen_to_world(anchor), fixed);
+        camera.zoom_at(0.0, anchor);
+        assert_eq!(camera.zoom, camera.min_zoom);
+        assert_near(camera.screen_to_world(anchor), fixed);
+    }
+
+    #[test]
+    fn clamp_to_bounds() {
+        let bounds = Rect::from_xywh(0.0, 0.0, 1000.0, 1000.0).expect("bounds");
+        let mut camera = Camera {
+            screen: (400, 200),
+            ..Camera::with_bounds(bounds)
+        };
+        camera.pan((-50.0, -50.0));
+        assert_eq!(camera.offset, (0.0, 0.0));
+        camera.pan((5000.0, 5000.0));
+        assert_eq!(camera.offset, (600.0, 800.0));
+        camera.look_at((500.0, 500.0));
+        assert_eq!(camera.offset, (300.0, 400.0));
+
+        // Bounds smaller than the view are centered
+        camera.zoom = 0.2;
+        camera.clamp();
+        assert_eq!(camera.offset, (-500.0, 0.0));
+
+        let mut free = Camera::default();
+        free.pan((-50.0, -50.0));
+        assert_eq!(free.offset, (-50.0, -50.0));
+    }
+
+    #[test]
+    fn edge_direction() {
+        let controls = CameraControls::default();
+        let screen = (800, 600);
+        assert_eq!(controls.edge_direction((400, 300), screen), (0.0, 0.0));
+        assert_eq!(controls.edge_direction((0, 300), screen), (-1.0, 0.0));
+        assert_eq!(controls.edge_direction((7, 592), screen), (-1.0, 1.0));
+        assert_eq!(controls.edge_direction((8, 591), screen), (0.0, 0.0));
+        assert_eq!(controls.edge_direction((799, 0), screen), (1.0, -1.0));
+        // Cursor over black bars is outside of the pixmap
+        assert_eq!(controls.edge_direction((-20, 900), screen), (-1.0, 1.0));
+
+        let disabled = CameraControls {
+            edge_margin: 0,
+            ..controls
+        };
+        assert_eq!(disabled.edge_direction((0, 0), screen), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::text::{TextRenderer, TextStyle};
+use std::time::{Duration, Instant};
+use strategka_core::Turn;
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+/// Counts events per second, the rate is updated once a second
+#[derive(Debug, Clone)]
+pub struct RateCounter {
+    count: u32,
+    window_start: Instant,
+    rate: f32,
+}
+
+impl Default for RateCounter {
+    fn default() -> Self {
+        RateCounter {
+            count: 0,
+            window_start: Instant::now(),
+            rate: 0.0,
+        }
+    }
+}
+
+impl RateCounter {
+    pub fn tick(&mut self) {
+        self.count += 1;
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.rate = self.count as f32 / elapsed.as_secs_f32();
+            self.count = 0;
+            self.window_start = Instant::now();
+        }
+    }
+
+    /// Events per second during the last full second
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+/// State of the render loop shown by the overlay
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DebugStatus {
+    pub turn: Turn,
+    /// Simulation turns per second the game is designed for
+    pub rate: u32,
+    /// Position of the replay playback and total turns of the replay
+    pub replay: Option<(Turn, Turn)>,
+    pub paused: bool,
+}
+
+/// Overlay with timings of the render loop drawn on top of the frame
+#[derive(Debug)]
+pub struct DebugOverlay {
+    pub visible: bool,
+    pub text: TextRenderer,
+    pub style: TextStyle,
+    render_rate: RateCounter,
+    simulation_rate: RateCounter,
+    frame_start: Instant,
+    /// Time spent on the last frame without waiting for the next tick
+    frame_time: Duration,
+}
+
+impl DebugOverlay {
+    pub fn new(visible: bool) -> Self {
+        DebugOverlay {
+            visible,
+            text: TextRenderer::default(),
+            style: TextStyle::new(Color::from_rgba8(230, 230, 230, 255)),
+            render_rate: RateCounter::default(),
+            simulation_rate: RateCounter::default(),
+            frame_start: Instant::now(),
+            frame_time: Duration::ZERO,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Mark start of frame work after waiting for the tick
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Instant::now();
+    }
+
+    /// Mark that a turn of simulation is done
+    pub fn simulated(&mut self) {
+        self.simulation_rate.tick();
+    }
+
+    /// Mark that the frame is presented
+    pub fn end_frame(&mut self) {
+        self.frame_time = self.frame_start.elapsed();
+        self.render_rate.tick();
+    }
+
+    /// Lines of the overlay, followed by the lines provided by the game
+    pub fn lines(&self, status: &DebugStatus, extra: Vec<String>) -> Vec<String> {
+        let mut lines = vec![format!("Turn: {}", status.turn)];
+        if let Some((position, total)) = status.replay {
+            lines.push(format!("Replay: {position}/{total}"));
+        }
+        let simulation_fps = self.simulation_rate.rate();
+        let speed = if status.paused {
+            0.0
+        } else {
+            simulation_fps / status.rate.max(1) as f32
+        };
+        lines.push(format!(
+            "Render FPS: {:.1}  Simulation FPS: {:.1}",
+            self.render_rate.rate(),
+            simulation_fps
+        ));
+        lines.push(format!(
+            "Frame time: {:.2} ms",
+            self.frame_time.as_secs_f32() * 1000.0
+        ));
+        lines.push(format!(
+            "Speed: x{speed:.2}{}",
+            if status.paused { "  PAUSED" } else { "" }
+        ));
+        lines.extend(extra);
+        lines
+    }
+
+    /// Draw the overlay in the top left corner of the frame if it is visible
+    pub fn draw(&self, pixmap: &mut Pixmap, status: &DebugStatus, extra: Vec<String>) {
+        if !self.visible {
+            return;
+        }
+        let text = self.lines(status, extra).join("\n");
+        let (width, height) = self.text.measure(&text, &self.style);
+        let padding = 4;
+        if let Some(background) = Rect::from_xywh(
+            0.0,
+            0.0,
+            (width + 2 * padding) as f32,
+            (height + 2 * padding) as f32,
+        ) {
+            let mut paint = Paint::default();
+            paint.set_color_rgba8(0, 0, 0, 160);
+            pixmap.fill_rect(background, &paint, Transform::identity(), None);
+        }
+        self.text
+            .draw(pixmap, &text, (padding as i32, padding as i32), &self.style);
+    }
+}
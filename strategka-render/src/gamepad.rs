@@ -0,0 +1,47 @@
+use sdl2::controller::GameController;
+use sdl2::event::Event;
+use sdl2::{GameControllerSubsystem, Sdl};
+use std::collections::HashMap;
+
+/// Keeps connected gamepads opened. SDL delivers controller events only for opened
+/// devices, the ones connected at startup are reported as added on the first poll.
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    /// Opened controllers by joystick instance id
+    opened: HashMap<u32, GameController>,
+}
+
+impl Gamepads {
+    pub fn new(sdl: &Sdl) -> Result<Self, String> {
+        Ok(Gamepads {
+            subsystem: sdl.game_controller()?,
+            opened: HashMap::new(),
+        })
+    }
+
+    /// Open and close controllers on hot plug events. Controllers that fail to open are
+    /// skipped, as a missing gamepad shouldn't stop the game.
+    pub fn handle_event(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                if let Ok(controller) = self.subsystem.open(which) {
+                    self.opened.insert(controller.instance_id(), controller);
+                }
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.opened.remove(&which);
+            }
+            _ => (),
+        }
+    }
+
+    /// Names of connected controllers
+    pub fn names(&self) -> Vec<String> {
+        self.opened.values().map(|c| c.name()).collect()
+    }
+
+    /// Subsystem to add custom controller mappings
+    pub fn subsystem(&self) -> &GameControllerSubsystem {
+        &self.subsystem
+    }
+}
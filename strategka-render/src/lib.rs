@@ -1,22 +1,74 @@
-use sdl2::EventPump;
-use sdl2::{event::Event, video::WindowBuildError};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::pixels::{Color as SdlColor, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+use sdl2::video::{FullscreenType, Window, WindowBuildError, WindowContext};
+use sdl2::{EventPump, Sdl};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::fmt::{Debug, Display};
 use std::path::PathBuf;
 use std::thread;
 use std::time;
-use strategka_core::Replay;
+use strategka_core::replay::error::ErrorOwned;
 use strategka_core::World;
+use strategka_core::{InputQueue, Playback, Replay, SaveGame, Tick, Turn};
 use thiserror::Error;
 use tiny_skia::*;
 
+pub mod bindings;
+pub mod camera;
+pub mod debug;
+pub mod gamepad;
+pub mod present;
+pub mod text;
+pub mod timeline;
+pub mod ui;
+
+pub use bindings::{
+    ActionEvent, ActionKind, ActionMapper, AxisSide, Binding, Bindings, BindingsError, Mods,
+    Trigger, DEBUG_ACTION, FULLSCREEN_ACTION, QUICKLOAD_ACTION, QUICKSAVE_ACTION, QUIT_ACTION,
+};
+pub use camera::{Camera, CameraControls};
+pub use debug::{DebugOverlay, DebugStatus};
+pub use gamepad::Gamepads;
+pub use present::Viewport;
+pub use text::{Align, BitmapFont, Glyph, TextRenderer, TextStyle};
+pub use timeline::Timeline;
+pub use ui::{Ui, UiFrame, UiStyle};
+
 pub struct RenderInfo {
+    /// Initial width of the window
     pub width: u32,
+    /// Initial height of the window
     pub height: u32,
     pub window_tittle: String,
     pub fps: u32,
     pub save_replay: Option<PathBuf>,
+    /// Amount of turns between polling of an input and applying it to the simulation.
+    /// Inputs are recorded in replay at the turn they are applied.
+    pub input_delay: Turn,
+    /// Where the quick save is stored. Saving and loading are disabled if not set.
+    pub save_game: Option<PathBuf>,
+    /// Mapping of keys and mouse buttons into actions that are passed to event handler.
+    /// [`QUICKSAVE_ACTION`] and [`QUICKLOAD_ACTION`] are handled by the render loop.
+    pub bindings: Bindings,
+    /// Fraction of gamepad axis range near the center that is ignored
+    pub deadzone: f32,
+    /// Allow user to resize the window
+    pub resizable: bool,
+    /// Start in fullscreen mode. [`FULLSCREEN_ACTION`] toggles it.
+    pub fullscreen: bool,
+    /// Use full resolution of HiDPI displays
+    pub high_dpi: bool,
+    /// Initial view into the world
+    pub camera: Camera,
+    /// Mouse control of the camera. The camera moves only by event handlers if not set.
+    pub camera_controls: Option<CameraControls>,
+    /// Show the debug overlay at start. [`DEBUG_ACTION`] toggles it.
+    pub debug_overlay: bool,
+    /// Scrub bar with input markers shown by [`replay_loop`], disabled if not set
+    pub timeline: Option<Timeline>,
 }
 
 impl RenderInfo {
@@ -27,6 +79,17 @@ impl RenderInfo {
             window_tittle: "Strategka".to_owned(),
             fps: 30,
             save_replay: None,
+            input_delay: 0,
+            save_game: None,
+            bindings: Bindings::default(),
+            deadzone: bindings::DEFAULT_DEADZONE,
+            resizable: true,
+            fullscreen: false,
+            high_dpi: true,
+            camera: Camera::default(),
+            camera_controls: None,
+            debug_overlay: false,
+            timeline: Some(Timeline::default()),
         }
     }
 }
@@ -45,14 +108,22 @@ pub enum Error<WE: Debug + Display> {
     VideoInit(String),
     #[error("Failed to create window: {0}")]
     WindowCreation(#[from] WindowBuildError),
+    #[error("Failed to init game controller subsystem: {0}")]
+    ControllerInit(String),
     #[error("Failed to create event pump: {0}")]
     EventPump(String),
-    #[error("Failed to get window surface: {0}")]
-    WindowSurface(String),
+    #[error("Failed to create renderer: {0}")]
+    Canvas(String),
+    #[error("Failed to create texture: {0}")]
+    Texture(String),
     #[error("Failed to blit result to window: {0}")]
     WindowFinish(String),
+    #[error("Failed to switch fullscreen mode: {0}")]
+    Fullscreen(String),
     #[error("Replay error: {0}")]
-    Replay(#[from] strategka_core::replay::error::ErrorOwned),
+    Replay(#[from] ErrorOwned),
+    #[error("Bindings error: {0}")]
+    Bindings(#[from] BindingsError),
     #[error("Event handler error: {0}")]
     EventHandler(WE),
     #[error("Input handler error: {0}")]
@@ -63,15 +134,109 @@ pub enum Error<WE: Debug + Display> {
     Render(WE),
 }
 
+/// Access to the state of render loop from event handlers
+pub struct EventContext<'a> {
+    /// Resolves raw events into actions, bindings can be changed at runtime
+    pub mapper: &'a mut ActionMapper,
+    /// Converts positions of actions into world coordinates
+    pub camera: &'a mut Camera,
+}
+
+/// Parameters of the frame that `render` should produce
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RenderContext {
+    /// Width of the window in pixels. Pixmap of that size is presented without scaling,
+    /// other sizes are scaled to fit the window with black bars.
+    pub width: u32,
+    /// Height of the window in pixels
+    pub height: u32,
+    /// Pixels per window coordinate, greater than 1 on HiDPI displays
+    pub dpi_scale: f32,
+    /// Size of the window is changed since the previous frame
+    pub resized: bool,
+    /// View into the world, use [`Camera::transform`] to draw world objects
+    pub camera: Camera,
+}
+
+/// Presentation state of the game that is passed to the render loops. Use `()` if the
+/// game doesn't need one.
+pub trait View<W: World> {
+    /// Lines that the game adds to the debug overlay
+    fn debug_lines(&self, _world: &W) -> Vec<String> {
+        vec![]
+    }
+
+    /// Declare widgets of the frame, they are drawn over the rendered world. Returned inputs
+    /// are scheduled like the ones of the event handler and ignored during replays.
+    ///
+    /// Widgets are declared after the inputs of the turn are applied, so with zero
+    /// `input_delay` their inputs arrive late and are applied at the next turn.
+    fn ui(&mut self, _world: &W, _ui: &mut UiFrame) -> Vec<W::Input> {
+        vec![]
+    }
+
+    /// Player that made the input, colors markers of the replay timeline
+    fn input_player(&self, _input: &W::Input) -> Option<usize> {
+        None
+    }
+
+    /// Called when the world is replaced by a loaded save. The view should drop the state
+    /// that refers to the old world, like selection.
+    fn loaded(&mut self, _world: &W) {}
+}
+
+impl<W: World> View<W> for () {}
+
 /// High level wrapper that starts endless loop of rendering
 ///
-/// - `event_handler` process events and turns them into inputs that are recored in the simulation, if returns 'true' the render loop exits
-/// - `input_handler` process inputs into simulation with mutation of state. All inputs are stored in replay.
-/// - `simulate` process one step of simulation.
-/// - `render` creates next frame.
-pub fn render_loop<E, I, S, R, W, Err>(
+/// `view` is presentation state of the game (selection, opened panels and so on). It is
+/// changed only by event handler and is never recorded in replays.
+///
+/// - `event_handler` process actions resolved by `info.bindings`, updates the view and turns actions into inputs that are recored in the simulation. The inputs are applied after `info.input_delay` turns.
+/// - `input_handler` process inputs into simulation with mutation of state, if returns 'true' the render loop exits. All inputs are stored in replay.
+/// - `simulate` process one step of simulation. The tick rate equals to `info.fps`.
+/// - `render` creates next frame from the world and the view. Mouse positions of actions are in pixmap coordinates of
+///   the last frame.
+pub fn render_loop<E, I, S, R, W, V, Err>(
     info: &RenderInfo,
-    mut state: W,
+    state: W,
+    view: &mut V,
+    event_handler: E,
+    input_handler: I,
+    simulate: S,
+    render: R,
+) -> Result<(), Error<Err>>
+where
+    W: World + Default + Clone + Serialize + DeserializeOwned,
+    E: FnMut(&W, &mut V, &ActionEvent, &mut EventContext) -> Result<Vec<W::Input>, Err>,
+    I: FnMut(&mut W, &W::Input) -> Result<bool, Err>,
+    S: FnMut(&mut W, Tick) -> Result<(), Err>,
+    R: FnMut(&W, &V, &RenderContext) -> Result<Pixmap, Err>,
+    V: View<W>,
+    Err: Debug + Display,
+{
+    resume_loop(
+        info,
+        SaveGame::new(&state, info.fps),
+        view,
+        event_handler,
+        input_handler,
+        simulate,
+        render,
+    )
+}
+
+/// The same as [`render_loop`], but continues simulation from the given save. The tick
+/// rate equals to the rate of the save, pending inputs of the save are applied at their turns.
+/// Ticks count turns from the start of the replay, which is the saved turn for saves
+/// without replay.
+///
+/// If `info.save_game` is set, [`QUICKSAVE_ACTION`] writes down current state of
+/// simulation with replay so far and [`QUICKLOAD_ACTION`] restores it.
+pub fn resume_loop<E, I, S, R, W, V, Err>(
+    info: &RenderInfo,
+    save: SaveGame<W>,
+    view: &mut V,
     mut event_handler: E,
     mut input_handler: I,
     mut simulate: S,
@@ -79,31 +244,22 @@ pub fn render_loop<E, I, S, R, W, Err>(
 ) -> Result<(), Error<Err>>
 where
     W: World + Default + Clone + Serialize + DeserializeOwned,
-    E: FnMut(&W, Event) -> Result<Vec<W::Input>, Err>,
+    E: FnMut(&W, &mut V, &ActionEvent, &mut EventContext) -> Result<Vec<W::Input>, Err>,
     I: FnMut(&mut W, &W::Input) -> Result<bool, Err>,
-    S: FnMut(&mut W, f32) -> Result<(), Err>,
-    R: FnMut(&W) -> Result<Pixmap, Err>,
+    S: FnMut(&mut W, Tick) -> Result<(), Err>,
+    R: FnMut(&W, &V, &RenderContext) -> Result<Pixmap, Err>,
+    V: View<W>,
     Err: Debug + Display,
 {
-    let sdl_context = sdl2::init().map_err(Error::SdlInit)?;
-    let video_subsystem = sdl_context.video().map_err(Error::VideoInit)?;
-
-    let window = video_subsystem
-        .window(&info.window_tittle, info.width, info.height)
-        .position_centered()
-        .build()?;
-
-    let mut replay = Replay::new(&state, info.fps);
-    let mut turn: u64 = 0;
+    let mut screen = Screen::new(info)?;
+    let mut session = Session::new(save, info.input_delay);
     let mut last_tick = time::Instant::now();
-    let mut event_pump = sdl_context.event_pump().map_err(Error::EventPump)?;
     'running: loop {
         let need_exit = process_input_events(
             info,
-            &mut state,
-            &mut replay,
-            turn,
-            &mut event_pump,
+            &mut session,
+            &mut screen,
+            view,
             &mut event_handler,
             &mut input_handler,
         )?;
@@ -111,28 +267,322 @@ where
             break 'running;
         }
 
-        let mut surface = window.surface(&event_pump).map_err(Error::WindowSurface)?;
-        let dt = ensure_fps(info.fps, &last_tick);
-        simulate(&mut state, dt).map_err(Error::Simulation)?;
-        let pixels = render(&mut state).map_err(Error::Render)?;
-
-        surface.with_lock_mut(|window_pixels| {
-            for (i, pixel) in pixels.pixels().iter().enumerate() {
-                let c = pixel.demultiply();
-                window_pixels[i * 4] = c.blue();
-                window_pixels[i * 4 + 1] = c.green();
-                window_pixels[i * 4 + 2] = c.red();
-                window_pixels[i * 4 + 3] = c.alpha();
-            }
-        });
-
-        surface.finish().map_err(Error::WindowFinish)?;
+        ensure_fps(info.fps, &last_tick);
+        screen.debug.begin_frame();
+        screen.update_camera();
+        let tick = session.tick();
+        simulate(&mut session.state, tick).map_err(Error::Simulation)?;
+        screen.debug.simulated();
+        let mut pixels =
+            render(&session.state, view, &screen.render_context()).map_err(Error::Render)?;
+        // Inputs of this turn are already taken, without delay these are applied late
+        let ui_inputs = view.ui(&session.state, &mut screen.ui.begin(&mut pixels));
+        session.queue.schedule(session.turn, ui_inputs);
+        let status = DebugStatus {
+            turn: session.turn,
+            rate: session.replay.rate,
+            replay: None,
+            paused: false,
+        };
+        screen.draw_debug(&mut pixels, &status, &session.state, view);
+        screen.present(&pixels)?;
+        screen.debug.end_frame();
         last_tick = time::Instant::now();
-        turn += 1;
+        session.turn += 1;
     }
     Ok(())
 }
 
+/// Window with everything that is needed to resolve actions and present frames
+struct Screen {
+    canvas: Canvas<Window>,
+    texture_creator: TextureCreator<WindowContext>,
+    /// Streaming texture the frames are uploaded to, recreated when pixmap size changes
+    texture: Option<(Texture, (u32, u32))>,
+    event_pump: EventPump,
+    gamepads: Gamepads,
+    mapper: ActionMapper,
+    /// Placement of the last presented frame
+    viewport: Viewport,
+    /// Window size in pixels that was reported to the last `render` call
+    last_size: (u32, u32),
+    camera: Camera,
+    camera_controls: Option<CameraControls>,
+    /// Cursor is inside the window, so edge scrolling is active
+    mouse_inside: bool,
+    last_frame: time::Instant,
+    ui: Ui,
+    debug: DebugOverlay,
+    // Dropped last as the other fields depend on the context
+    _sdl: Sdl,
+}
+
+impl Screen {
+    fn new<Err: Debug + Display>(info: &RenderInfo) -> Result<Self, Error<Err>> {
+        let sdl = sdl2::init().map_err(Error::SdlInit)?;
+        let video_subsystem = sdl.video().map_err(Error::VideoInit)?;
+
+        let mut builder = video_subsystem.window(&info.window_tittle, info.width, info.height);
+        builder.position_centered();
+        if info.resizable {
+            builder.resizable();
+        }
+        if info.fullscreen {
+            builder.fullscreen_desktop();
+        }
+        if info.high_dpi {
+            builder.allow_highdpi();
+        }
+        let mut canvas = builder
+            .build()?
+            .into_canvas()
+            .build()
+            .map_err(|e| Error::Canvas(e.to_string()))?;
+        canvas.set_draw_color(SdlColor::BLACK);
+
+        let mut mapper = ActionMapper::new(info.bindings.clone());
+        mapper.deadzone = info.deadzone;
+        let size = (info.width, info.height);
+        Ok(Screen {
+            texture_creator: canvas.texture_creator(),
+            canvas,
+            texture: None,
+            event_pump: sdl.event_pump().map_err(Error::EventPump)?,
+            gamepads: Gamepads::new(&sdl).map_err(Error::ControllerInit)?,
+            mapper,
+            viewport: Viewport::fit(size, size, 1.0),
+            last_size: size,
+            camera: info.camera,
+            camera_controls: info.camera_controls,
+            mouse_inside: false,
+            last_frame: time::Instant::now(),
+            ui: Ui::default(),
+            debug: DebugOverlay::new(info.debug_overlay),
+            _sdl: sdl,
+        })
+    }
+
+    /// Take all pending events and resolve them into actions with mouse positions in
+    /// pixmap coordinates. [`FULLSCREEN_ACTION`] and [`DEBUG_ACTION`] are handled here.
+    /// Events consumed by the UI don't produce actions.
+    fn poll_actions<Err: Debug + Display>(&mut self) -> Result<Vec<ActionEvent>, Error<Err>> {
+        let events: Vec<_> = self.event_pump.poll_iter().collect();
+        let mut actions = vec![];
+        for event in events {
+            self.gamepads.handle_event(&event);
+            if self.ui.handle_event(&event, &self.viewport) {
+                continue;
+            }
+            self.control_camera(&event);
+            for mut action in self.mapper.map_event(&event) {
+                if action.pressed(FULLSCREEN_ACTION) {
+                    self.toggle_fullscreen()?;
+                    continue;
+                }
+                if action.pressed(DEBUG_ACTION) {
+                    self.debug.toggle();
+                    continue;
+                }
+                action.position = self.viewport.window_to_pixmap(action.position);
+                actions.push(action);
+            }
+        }
+        Ok(actions)
+    }
+
+    /// Zoom the camera by mouse wheel and drag it with mouse button
+    fn control_camera(&mut self, event: &Event) {
+        let controls = match self.camera_controls {
+            Some(controls) => controls,
+            None => return,
+        };
+        match *event {
+            Event::MouseWheel { y, .. } if controls.wheel_zoom != 1.0 => {
+                let (x, y_pos) = self.viewport.window_to_pixmap(self.mapper.position());
+                let factor = controls.wheel_zoom.powi(y);
+                self.camera.zoom_at(factor, (x as f32, y_pos as f32));
+            }
+            Event::MouseMotion {
+                mousestate,
+                x,
+                y,
+                xrel,
+                yrel,
+                ..
+            } => {
+                self.mouse_inside = true;
+                let dragged = controls
+                    .drag_button
+                    .is_some_and(|button| mousestate.is_mouse_button_pressed(button));
+                if dragged {
+                    let from = self.viewport.window_to_pixmap((x - xrel, y - yrel));
+                    let to = self.viewport.window_to_pixmap((x, y));
+                    self.camera
+                        .pan(((from.0 - to.0) as f32, (from.1 - to.1) as f32));
+                }
+            }
+            Event::Window {
+                win_event: WindowEvent::Leave | WindowEvent::FocusLost,
+                ..
+            } => self.mouse_inside = false,
+            _ => (),
+        }
+    }
+
+    /// Scroll the camera when the cursor is near the window edge
+    fn scroll_camera(&mut self) {
+        let dt = self.last_frame.elapsed().as_secs_f32();
+        self.last_frame = time::Instant::now();
+        let controls = match self.camera_controls {
+            Some(controls) if self.mouse_inside => controls,
+            _ => return,
+        };
+        let cursor = self.viewport.window_to_pixmap(self.mapper.position());
+        let (dx, dy) = controls.edge_direction(cursor, self.viewport.source);
+        if dx != 0.0 || dy != 0.0 {
+            let distance = controls.edge_speed * dt;
+            self.camera.pan((dx * distance, dy * distance));
+        }
+    }
+
+    fn toggle_fullscreen<Err: Debug + Display>(&mut self) -> Result<(), Error<Err>> {
+        let window = self.canvas.window_mut();
+        let mode = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
+        window.set_fullscreen(mode).map_err(Error::Fullscreen)
+    }
+
+    /// Fit the camera to the drawable size and scroll it by the cursor at the window edge.
+    /// Called once per frame before the frame is rendered.
+    fn update_camera(&mut self) {
+        self.camera.screen = self.canvas.window().drawable_size();
+        self.camera.clamp();
+        self.scroll_camera();
+    }
+
+    fn render_context(&mut self) -> RenderContext {
+        let (width, height) = self.canvas.window().drawable_size();
+        let (window_width, _) = self.canvas.window().size();
+        let resized = self.last_size != (width, height);
+        self.last_size = (width, height);
+        RenderContext {
+            width,
+            height,
+            dpi_scale: width as f32 / window_width.max(1) as f32,
+            resized,
+            camera: self.camera,
+        }
+    }
+
+    /// Draw the debug overlay over the frame if it is enabled
+    fn draw_debug<W: World, V: View<W>>(
+        &self,
+        pixmap: &mut Pixmap,
+        status: &DebugStatus,
+        world: &W,
+        view: &V,
+    ) {
+        if self.debug.visible {
+            self.debug.draw(pixmap, status, view.debug_lines(world));
+        }
+    }
+
+    /// Upload the frame into streaming texture and draw it scaled to fit the window.
+    /// Pixels are premultiplied, so translucent pixels appear as blended over black.
+    fn present<Err: Debug + Display>(&mut self, pixmap: &Pixmap) -> Result<(), Error<Err>> {
+        let size = (pixmap.width(), pixmap.height());
+        let texture = match &mut self.texture {
+            Some((texture, texture_size)) if *texture_size == size => texture,
+            _ => {
+                let mut texture = self
+                    .texture_creator
+                    .create_texture_streaming(PixelFormatEnum::RGBA32, size.0, size.1)
+                    .map_err(|e| Error::Texture(e.to_string()))?;
+                texture.set_blend_mode(BlendMode::None);
+                if let Some((old, _)) = self.texture.replace((texture, size)) {
+                    // Safety: the renderer that created the texture is still alive
+                    unsafe { old.destroy() };
+                }
+                &mut self.texture.as_mut().expect("texture is just created").0
+            }
+        };
+        let row_bytes = size.0 as usize * 4;
+        texture
+            .with_lock(None, |pixels, pitch| {
+                present::copy_rows(pixmap.data(), row_bytes, pixels, pitch)
+            })
+            .map_err(Error::WindowFinish)?;
+
+        let output = self.canvas.output_size().map_err(Error::WindowFinish)?;
+        let (window_width, _) = self.canvas.window().size();
+        self.viewport = Viewport::fit(size, output, output.0 as f32 / window_width.max(1) as f32);
+        let target = Rect::new(
+            self.viewport.x as i32,
+            self.viewport.y as i32,
+            self.viewport.width,
+            self.viewport.height,
+        );
+        self.canvas.clear();
+        self.canvas
+            .copy(texture, None, Some(target))
+            .map_err(Error::WindowFinish)?;
+        self.canvas.present();
+        Ok(())
+    }
+}
+
+/// State of live simulation that is maintained by the render loop
+struct Session<W: World> {
+    state: W,
+    replay: Replay<W>,
+    queue: InputQueue<W::Input>,
+    turn: Turn,
+    /// Turn of simulation that is the first turn of the replay
+    replay_start: Turn,
+}
+
+impl<W: World + Default + Clone + Serialize + DeserializeOwned> Session<W> {
+    fn new(save: SaveGame<W>, input_delay: Turn) -> Self {
+        let turn = save.turn;
+        let replay_start = save.turn - save.replay_turn();
+        let state = save.world.clone();
+        let queue = save.input_queue(input_delay);
+        Session {
+            state,
+            replay: save.into_replay(),
+            queue,
+            turn,
+            replay_start,
+        }
+    }
+
+    /// Tick of the current turn. Turns are counted from the start of the replay, so
+    /// playback of the replay simulates the same ticks.
+    fn tick(&self) -> Tick {
+        Tick::new(self.turn - self.replay_start, self.replay.rate)
+    }
+
+    /// Make save of the current state with inputs that are not applied yet. The replay
+    /// is stored only if it covers the whole simulation.
+    fn save_game(&self) -> SaveGame<W> {
+        let replay = (self.replay_start == 0).then(|| {
+            let mut replay = self.replay.clone();
+            replay.total_turns = self.turn;
+            replay
+        });
+        SaveGame {
+            rate: self.replay.rate,
+            turn: self.turn,
+            world: self.state.clone(),
+            replay,
+            pending: vec![],
+        }
+        .with_pending(&self.queue)
+    }
+}
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum ReplayControl {
     PauseSimulation,
@@ -140,182 +590,303 @@ pub enum ReplayControl {
     ToggleSimulation,
     RestartSimulation,
     EndReplay,
+    /// Stop playback at the current turn and continue the simulation with new inputs
+    TakeControl,
 }
 
 /// High level wrapper that starts endless loop of rendering based on replay.
 ///
+/// - `event_handler` updates the view and turns actions resolved by `info.bindings` into
+///   replay controls. The view is left to the caller to continue with after taking control.
 /// - `input_handler` process inputs into simulation with mutation of state.
-/// - `simulate` process one step of simulation.
-pub fn replay_loop<E, I, S, R, W, Err>(
+/// - `simulate` process one step of simulation. The tick rate equals to the rate of replay.
+///
+/// Dragging `info.timeline` pauses the playback and seeks to the turn under the cursor.
+/// Seeking backwards resimulates the replay from the start.
+///
+/// Returns save of the current turn when [`ReplayControl::TakeControl`] is received. The
+/// save contains the replay up to the turn, pass it to [`resume_loop`] to continue the simulation
+/// with live inputs.
+pub fn replay_loop<E, I, S, R, W, V, Err>(
     info: &RenderInfo,
     replay: &Replay<W>,
+    view: &mut V,
     mut event_handler: E,
     mut input_handler: I,
     mut simulate: S,
     mut render: R,
-) -> Result<(), Error<Err>>
+) -> Result<Option<SaveGame<W>>, Error<Err>>
 where
     W: World + Default + Clone + Serialize + DeserializeOwned,
-    E: FnMut(&W, Event) -> Result<Option<ReplayControl>, Err>,
+    E: FnMut(&W, &mut V, &ActionEvent, &mut EventContext) -> Result<Option<ReplayControl>, Err>,
     I: FnMut(&mut W, &W::Input) -> Result<bool, Err>,
-    S: FnMut(&mut W, f32) -> Result<(), Err>,
-    R: FnMut(&W) -> Result<Pixmap, Err>,
+    S: FnMut(&mut W, Tick) -> Result<(), Err>,
+    R: FnMut(&W, &V, &RenderContext) -> Result<Pixmap, Err>,
+    V: View<W>,
     Err: Debug + Display,
 {
-    let sdl_context = sdl2::init().map_err(Error::SdlInit)?;
-    let video_subsystem = sdl_context.video().map_err(Error::VideoInit)?;
-
-    let window = video_subsystem
-        .window(&info.window_tittle, info.width, info.height)
-        .position_centered()
-        .build()?;
-
-    let mut state = replay.initial.clone();
-    let mut turn: u64 = 0;
-    let mut replay_turn: usize = 0;
+    let mut screen = Screen::new(info)?;
+    let mut input_handler =
+        |world: &mut W, input: &W::Input| input_handler(world, input).map_err(Error::InputHandler);
+    let mut simulate = |world: &mut W, tick: Tick| simulate(world, tick).map_err(Error::Simulation);
+    let mut playback = SeekablePlayback::new(replay);
     let mut last_tick = time::Instant::now();
-    let mut event_pump = sdl_context.event_pump().map_err(Error::EventPump)?;
-    let mut stop_simulation = false;
+    let mut paused = false;
     'running: loop {
-        for event in event_pump.poll_iter() {
-            match event_handler(&state, event).map_err(Error::EventHandler)? {
+        for action in screen.poll_actions()? {
+            let mut ctx = EventContext {
+                mapper: &mut screen.mapper,
+                camera: &mut screen.camera,
+            };
+            let state = playback.current.state();
+            match event_handler(state, view, &action, &mut ctx).map_err(Error::EventHandler)? {
                 Some(ReplayControl::EndReplay) => {
                     break 'running;
                 }
+                Some(ReplayControl::TakeControl) => {
+                    let turn = playback.current.turn();
+                    // Inputs of the current turn are applied only at the end of replay
+                    let mut branch = replay.clone();
+                    branch.truncate(if playback.current.inputs_applied() {
+                        turn + 1
+                    } else {
+                        turn
+                    });
+                    return Ok(Some(SaveGame {
+                        rate: replay.rate,
+                        turn,
+                        world: playback.current.into_state(),
+                        replay: Some(branch),
+                        pending: vec![],
+                    }));
+                }
                 Some(ReplayControl::PauseSimulation) => {
-                    stop_simulation = true;
+                    paused = true;
                 }
                 Some(ReplayControl::UnpauseSimulation) => {
-                    if turn < replay.total_turns {
-                        stop_simulation = false;
-                    }
+                    paused = false;
                 }
                 Some(ReplayControl::ToggleSimulation) => {
-                    if turn < replay.total_turns {
-                        stop_simulation = !stop_simulation;
-                    }
+                    paused = !paused;
                 }
                 Some(ReplayControl::RestartSimulation) => {
-                    state = replay.initial.clone();
-                    turn = 0;
-                    replay_turn = 0;
-                    stop_simulation = false;
+                    playback.current = Playback::new(replay);
+                    paused = false;
                 }
                 _ => (),
             }
         }
 
-        let turn_inputs = if replay_turn < replay.inputs.len() {
-            let next_inputs = &replay.inputs[replay_turn];
-            if next_inputs.0 == turn {
-                replay_turn += 1;
-                Some(&next_inputs.1)
-            } else {
-                None
-            }
-        } else {
-            None
+        ensure_fps(info.fps, &last_tick);
+        screen.debug.begin_frame();
+        screen.update_camera();
+        if !paused && playback.step(&mut input_handler, &mut simulate)? {
+            screen.debug.simulated();
+        }
+        let state = playback.current.state();
+        let turn = playback.current.turn();
+        let mut pixels = render(state, view, &screen.render_context()).map_err(Error::Render)?;
+        let seek = {
+            let mut frame = screen.ui.begin(&mut pixels);
+            // Replay can't take new inputs
+            view.ui(state, &mut frame);
+            info.timeline.as_ref().and_then(|timeline| {
+                timeline.show(&mut frame, replay, turn, |input| view.input_player(input))
+            })
+        };
+        let status = DebugStatus {
+            turn,
+            rate: replay.rate,
+            replay: Some((turn, replay.total_turns)),
+            paused: paused || playback.current.is_stopped(),
         };
+        screen.draw_debug(&mut pixels, &status, state, view);
+        screen.present(&pixels)?;
+        screen.debug.end_frame();
+        last_tick = time::Instant::now();
 
-        if let Some(inputs) = turn_inputs {
-            let mut need_exit = false;
-            for input in inputs {
-                need_exit = input_handler(&mut state, input).map_err(Error::InputHandler)?;
-            }
-            if need_exit {
-                stop_simulation = true;
-            }
+        if let Some(target) = seek {
+            paused = true;
+            playback.seek(target, &mut input_handler, &mut simulate)?;
         }
+    }
+    Ok(None)
+}
+
+/// Amount of turns between snapshots of [`SeekablePlayback`]
+const SNAPSHOT_INTERVAL: Turn = 256;
 
-        let mut surface = window.surface(&event_pump).map_err(Error::WindowSurface)?;
-        let dt = ensure_fps(info.fps, &last_tick);
-        if !stop_simulation {
-            simulate(&mut state, dt).map_err(Error::Simulation)?;
-            turn += 1;
+/// Playback of the replay that remembers its state every [`SNAPSHOT_INTERVAL`] turns, so
+/// seeking resimulates the replay only from the closest snapshot.
+struct SeekablePlayback<'a, W: World> {
+    current: Playback<'a, W>,
+    /// Snapshot `i` is taken at turn `(i + 1) * SNAPSHOT_INTERVAL`
+    snapshots: Vec<Playback<'a, W>>,
+}
+
+impl<'a, W: World + Clone> SeekablePlayback<'a, W> {
+    fn new(replay: &'a Replay<W>) -> Self {
+        SeekablePlayback {
+            current: Playback::new(replay),
+            snapshots: vec![],
         }
-        let pixels = render(&mut state).map_err(Error::Render)?;
-
-        surface.with_lock_mut(|window_pixels| {
-            for (i, pixel) in pixels.pixels().iter().enumerate() {
-                let c = pixel.demultiply();
-                window_pixels[i * 4] = c.blue();
-                window_pixels[i * 4 + 1] = c.green();
-                window_pixels[i * 4 + 2] = c.red();
-                window_pixels[i * 4 + 3] = c.alpha();
-            }
-        });
+    }
 
-        surface.finish().map_err(Error::WindowFinish)?;
-        last_tick = time::Instant::now();
-        if turn >= replay.total_turns {
-            stop_simulation = true;
-            turn = replay.total_turns;
+    /// Step the playback and take snapshot when it reaches the next one
+    fn step<I, S, E>(&mut self, input_handler: I, simulate: S) -> Result<bool, E>
+    where
+        I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+        S: FnMut(&mut W, Tick) -> Result<(), E>,
+    {
+        let stepped = self.current.step(input_handler, simulate)?;
+        let next_snapshot = (self.snapshots.len() as Turn + 1) * SNAPSHOT_INTERVAL;
+        if stepped && self.current.turn() == next_snapshot {
+            self.snapshots.push(self.current.clone());
         }
+        Ok(stepped)
+    }
+
+    /// Simulate the replay up to the `target` turn starting from the closest snapshot
+    /// before it, unless the current turn is closer. Stops early if the playback stops.
+    fn seek<I, S, E>(
+        &mut self,
+        target: Turn,
+        mut input_handler: I,
+        mut simulate: S,
+    ) -> Result<(), E>
+    where
+        I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+        S: FnMut(&mut W, Tick) -> Result<(), E>,
+    {
+        let snapshot = (target / SNAPSHOT_INTERVAL).min(self.snapshots.len() as Turn) as usize;
+        let snapshot_turn = snapshot as Turn * SNAPSHOT_INTERVAL;
+        let turn = self.current.turn();
+        if target < turn || snapshot_turn > turn {
+            self.current = match snapshot.checked_sub(1) {
+                Some(i) => self.snapshots[i].clone(),
+                None => Playback::new(self.current.replay()),
+            };
+        }
+        while self.current.turn() < target && self.step(&mut input_handler, &mut simulate)? {}
+        Ok(())
     }
-    Ok(())
 }
 
-/// Helper to process all events from outside of simulation, turn them into inputs and apply them to simulation.
-/// Also, the function mantains record of all inputs inside the replay structure.
-fn process_input_events<W, E, I, Err>(
+/// Helper to process all events from outside of simulation, turn them into inputs and schedule them in the queue.
+/// Inputs that are scheduled for the current turn are applied to simulation.
+/// Also, the function mantains record of all applied inputs inside the replay structure.
+fn process_input_events<W, V, E, I, Err>(
     info: &RenderInfo,
-    state: &mut W,
-    replay: &mut Replay<W>,
-    turn: u64,
-    event_pump: &mut EventPump,
+    session: &mut Session<W>,
+    screen: &mut Screen,
+    view: &mut V,
     event_handler: &mut E,
     input_handler: &mut I,
 ) -> Result<bool, Error<Err>>
 where
     W: World + Default + Clone + Serialize + DeserializeOwned,
-    E: FnMut(&W, Event) -> Result<Vec<W::Input>, Err>,
+    E: FnMut(&W, &mut V, &ActionEvent, &mut EventContext) -> Result<Vec<W::Input>, Err>,
     I: FnMut(&mut W, &W::Input) -> Result<bool, Err>,
+    V: View<W>,
     Err: Debug + Display,
 {
-    let mut inputs = vec![];
-    let mut need_exit = false;
-    let mut save_error_replay = |inputs: &[W::Input]| {
-        if !inputs.is_empty() {
-            replay.record(turn, inputs).map_err(|e| e.into_owned())?;
-        }
-        replay.total_turns = turn;
-        if let Some(replay_path) = &info.save_replay {
-            replay.save(replay_path).map_err(|e| e.into_owned())?;
+    for action in screen.poll_actions()? {
+        if let Some(save_path) = &info.save_game {
+            if action.pressed(QUICKSAVE_ACTION) {
+                session
+                    .save_game()
+                    .save(save_path)
+                    .map_err(|e| e.into_owned())?;
+                continue;
+            }
+            if action.pressed(QUICKLOAD_ACTION) {
+                // Missing or broken save shouldn't end the game, the key press is ignored
+                match SaveGame::load(save_path) {
+                    Ok(save) => {
+                        *session = Session::new(save, info.input_delay);
+                        view.loaded(&session.state);
+                    }
+                    Err(e) => log::error!("Cannot load {}: {e}", save_path.display()),
+                }
+                continue;
+            }
         }
-        Result::<_, Error<Err>>::Ok(())
-    };
-    for event in event_pump.poll_iter() {
-        match event_handler(state, event).map_err(Error::EventHandler) {
+        let mut ctx = EventContext {
+            mapper: &mut screen.mapper,
+            camera: &mut screen.camera,
+        };
+        match event_handler(&session.state, view, &action, &mut ctx).map_err(Error::EventHandler) {
             Ok(new_inputs) => {
-                let exit = apply_inputs(
-                    state,
-                    &mut inputs,
-                    new_inputs,
-                    input_handler,
-                    &mut save_error_replay,
-                )?;
-                if exit {
-                    need_exit = true;
-                }
+                session.queue.schedule(session.turn, new_inputs);
             }
             Err(e) => {
-                save_error_replay(&inputs)?;
+                save_replay(
+                    info,
+                    &mut session.replay,
+                    session.turn - session.replay_start,
+                    &[],
+                )?;
                 return Err(e);
             }
         }
     }
-    if !inputs.is_empty() {
-        replay.record(turn, &inputs).map_err(|e| e.into_owned())?;
-    }
+    let replay_turn = session.turn - session.replay_start;
+    let mut inputs = vec![];
+    let need_exit = apply_inputs(
+        &mut session.state,
+        &mut inputs,
+        session.queue.take(session.turn),
+        input_handler,
+        |inputs: &[W::Input]| save_replay(info, &mut session.replay, replay_turn, inputs),
+    )?;
+    record_inputs(&mut session.replay, replay_turn, &inputs)?;
     if need_exit {
-        replay.total_turns = turn;
-        if let Some(replay_path) = &info.save_replay {
-            replay.save(replay_path).map_err(|e| e.into_owned())?;
-        }
+        save_replay(info, &mut session.replay, replay_turn, &[])?;
     }
     Ok(need_exit)
 }
 
+/// Record last inputs and write down the replay if `info.save_replay` is set
+fn save_replay<W, Err>(
+    info: &RenderInfo,
+    replay: &mut Replay<W>,
+    turn: Turn,
+    inputs: &[W::Input],
+) -> Result<(), Error<Err>>
+where
+    W: World + Default + Clone + Serialize + DeserializeOwned,
+    Err: Debug + Display,
+{
+    record_inputs(replay, turn, inputs)?;
+    replay.total_turns = turn;
+    if let Some(replay_path) = &info.save_replay {
+        replay.save(replay_path).map_err(|e| e.into_owned())?;
+    }
+    Ok(())
+}
+
+/// Record inputs of the turn in the replay. If the replay already has inputs for the turn,
+/// the new ones are appended. That happens when simulation continues from the end of a replay.
+fn record_inputs<W>(
+    replay: &mut Replay<W>,
+    turn: Turn,
+    inputs: &[W::Input],
+) -> Result<(), ErrorOwned>
+where
+    W: World + Default + Clone + Serialize + DeserializeOwned,
+{
+    if inputs.is_empty() {
+        return Ok(());
+    }
+    match replay.inputs.last_mut() {
+        Some((last_turn, last_inputs)) if *last_turn == turn => {
+            last_inputs.extend_from_slice(inputs);
+            Ok(())
+        }
+        _ => replay.record(turn, inputs).map_err(|e| e.into_owned()),
+    }
+}
+
 /// Apply given portion of simulation inputs and store them in `inputs`.
 fn apply_inputs<W, InputIter, I, SI, Err>(
     state: &mut W,
@@ -350,7 +921,7 @@ where
 }
 
 // Helper to run loop with given frames per second
-fn ensure_fps(fps: u32, last_tick: &time::Instant) -> f32 {
+fn ensure_fps(fps: u32, last_tick: &time::Instant) {
     let t = last_tick.elapsed();
     let passed_nano = t.as_secs() * 1_000_000_000 + t.subsec_nanos() as u64;
     let fps_dt = (1. / fps as f32) * 1_000_000_000.;
@@ -358,5 +929,114 @@ fn ensure_fps(fps: u32, last_tick: &time::Instant) -> f32 {
     if diff > 0.0 {
         thread::sleep(time::Duration::new(0, diff as u32))
     };
-    fps_dt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, serde::Deserialize)]
+    struct Counter {
+        value: i64,
+        ticks: u64,
+    }
+
+    impl World for Counter {
+        type Input = i64;
+
+        fn magic_bytes() -> [u8; 4] {
+            *b"TCNT"
+        }
+
+        fn current_version() -> u32 {
+            1
+        }
+    }
+
+    fn add(world: &mut Counter, input: &i64) -> Result<bool, Infallible> {
+        world.value += input;
+        Ok(false)
+    }
+
+    /// Simulation that depends on the turn number
+    fn sum_turns(world: &mut Counter, tick: Tick) -> Result<(), Infallible> {
+        world.ticks += tick.turn;
+        Ok(())
+    }
+
+    #[test]
+    fn resumed_session_replay() {
+        let save = SaveGame {
+            rate: 60,
+            turn: 50,
+            world: Counter::default(),
+            replay: None,
+            pending: vec![],
+        };
+        let mut session = Session::new(save, 0);
+        for i in 0..10 {
+            if i % 3 == 0 {
+                session.queue.schedule(session.turn, [i]);
+            }
+            let replay_turn = session.turn - session.replay_start;
+            let inputs = session.queue.take(session.turn);
+            for input in inputs.iter() {
+                add(&mut session.state, input).unwrap();
+            }
+            record_inputs(&mut session.replay, replay_turn, &inputs).expect("record");
+            let tick = session.tick();
+            sum_turns(&mut session.state, tick).unwrap();
+            session.turn += 1;
+        }
+        session.replay.total_turns = session.turn - session.replay_start;
+
+        let mut playback = Playback::new(&session.replay);
+        playback.advance_to(10, add, sum_turns).unwrap();
+        assert_eq!(playback.turn(), 10);
+        assert_eq!(playback.state(), &session.state);
+    }
+
+    #[test]
+    fn seek_from_snapshots() {
+        let mut replay = Replay::new(&Counter::default(), 60);
+        for turn in (0..1000).step_by(7) {
+            replay.record(turn, &[turn as i64]).expect("record");
+        }
+        replay.total_turns = 1000;
+        let expected = |turn: Turn| {
+            let mut playback = Playback::new(&replay);
+            playback
+                .advance_to(turn, add, |w: &mut Counter, _| {
+                    w.ticks += 1;
+                    Ok(())
+                })
+                .unwrap();
+            playback.into_state()
+        };
+
+        let mut playback = SeekablePlayback::new(&replay);
+        let mut simulated = 0;
+        let mut seek = |playback: &mut SeekablePlayback<Counter>, target| {
+            simulated = 0;
+            let simulate = |w: &mut Counter, _| {
+                w.ticks += 1;
+                simulated += 1;
+                Ok::<_, Infallible>(())
+            };
+            playback.seek(target, add, simulate).unwrap();
+            assert_eq!(playback.current.turn(), target);
+            assert_eq!(playback.current.state(), &expected(target));
+            simulated
+        };
+
+        assert_eq!(seek(&mut playback, 900), 900);
+        assert_eq!(playback.snapshots.len(), 3);
+        // Backwards from the closest snapshot, or from the start before the first one
+        assert_eq!(seek(&mut playback, 300), 300 - 256);
+        assert_eq!(seek(&mut playback, 10), 10);
+        // Forward from a snapshot that is closer than the current turn
+        assert_eq!(seek(&mut playback, 800), 800 - 768);
+        assert_eq!(seek(&mut playback, 810), 10);
+    }
 }
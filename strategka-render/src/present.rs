@@ -0,0 +1,145 @@
+/// Placement of the rendered pixmap inside the window. The pixmap is scaled
+/// to fit the window keeping the aspect ratio, the rest is filled with black bars.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    /// Left edge of the picture in window pixels
+    pub x: u32,
+    /// Top edge of the picture in window pixels
+    pub y: u32,
+    /// Width of the picture in window pixels
+    pub width: u32,
+    /// Height of the picture in window pixels
+    pub height: u32,
+    /// Size of the presented pixmap
+    pub source: (u32, u32),
+    /// Window pixels per window coordinate
+    pub dpi_scale: f32,
+}
+
+impl Viewport {
+    /// Fit picture of `source` size into the window output of `target` size
+    pub fn fit(source: (u32, u32), target: (u32, u32), dpi_scale: f32) -> Self {
+        let (sw, sh) = (source.0.max(1) as u64, source.1.max(1) as u64);
+        let (tw, th) = (target.0 as u64, target.1 as u64);
+        // Compare tw/sw with th/sh without floating point
+        let (width, height) = if tw * sh <= th * sw {
+            (tw, tw * sh / sw)
+        } else {
+            (th * sw / sh, th)
+        };
+        Viewport {
+            x: ((tw - width) / 2) as u32,
+            y: ((th - height) / 2) as u32,
+            width: width as u32,
+            height: height as u32,
+            source,
+            dpi_scale,
+        }
+    }
+
+    /// Convert point in window coordinates (as reported by mouse events) into pixmap
+    /// coordinates. Points over black bars are outside of the pixmap bounds.
+    pub fn window_to_pixmap(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        let px = x as f32 * self.dpi_scale - self.x as f32;
+        let py = y as f32 * self.dpi_scale - self.y as f32;
+        let sx = self.source.0 as f32 / self.width.max(1) as f32;
+        let sy = self.source.1 as f32 / self.height.max(1) as f32;
+        ((px * sx).floor() as i32, (py * sy).floor() as i32)
+    }
+
+    /// Convert point in pixmap coordinates into window coordinates
+    pub fn pixmap_to_window(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        let sx = self.width as f32 / self.source.0.max(1) as f32;
+        let sy = self.height as f32 / self.source.1.max(1) as f32;
+        let px = x as f32 * sx + self.x as f32;
+        let py = y as f32 * sy + self.y as f32;
+        (
+            (px / self.dpi_scale).floor() as i32,
+            (py / self.dpi_scale).floor() as i32,
+        )
+    }
+}
+
+/// Copy rows of `row_bytes` length from tightly packed `source` into `target` that has
+/// rows of `pitch` bytes. Used to upload premultiplied RGBA pixels of the pixmap into
+/// streaming texture without per pixel conversion.
+pub fn copy_rows(source: &[u8], row_bytes: usize, target: &mut [u8], pitch: usize) {
+    if row_bytes == pitch {
+        let len = source.len().min(target.len());
+        target[..len].copy_from_slice(&source[..len]);
+        return;
+    }
+    for (src, dst) in source.chunks_exact(row_bytes).zip(target.chunks_mut(pitch)) {
+        let len = row_bytes.min(dst.len());
+        dst[..len].copy_from_slice(&src[..len]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_same_aspect() {
+        let viewport = Viewport::fit((320, 200), (640, 400), 1.0);
+        assert_eq!((viewport.x, viewport.y), (0, 0));
+        assert_eq!((viewport.width, viewport.height), (640, 400));
+        assert_eq!(viewport.window_to_pixmap((0, 0)), (0, 0));
+        assert_eq!(viewport.window_to_pixmap((639, 399)), (319, 199));
+        assert_eq!(viewport.pixmap_to_window((160, 100)), (320, 200));
+    }
+
+    #[test]
+    fn letterbox() {
+        // Wide window gets bars on the sides
+        let viewport = Viewport::fit((320, 200), (800, 400), 1.0);
+        assert_eq!((viewport.x, viewport.y), (80, 0));
+        assert_eq!((viewport.width, viewport.height), (640, 400));
+        assert_eq!(viewport.window_to_pixmap((80, 0)), (0, 0));
+        assert_eq!(viewport.window_to_pixmap((79, 10)).0, -1);
+        assert_eq!(viewport.window_to_pixmap((720, 10)).0, 320);
+
+        // Tall window gets bars on top and bottom
+        let viewport = Viewport::fit((320, 200), (320, 300), 1.0);
+        assert_eq!((viewport.x, viewport.y), (0, 50));
+        assert_eq!((viewport.width, viewport.height), (320, 200));
+        assert_eq!(viewport.window_to_pixmap((10, 49)), (10, -1));
+        assert_eq!(viewport.window_to_pixmap((10, 250)), (10, 200));
+        assert_eq!(viewport.pixmap_to_window((0, 0)), (0, 50));
+    }
+
+    #[test]
+    fn hidpi() {
+        // Window of 400x250 points with output of 800x500 pixels
+        let viewport = Viewport::fit((320, 200), (800, 500), 2.0);
+        assert_eq!((viewport.width, viewport.height), (800, 500));
+        assert_eq!(viewport.window_to_pixmap((200, 125)), (160, 100));
+        assert_eq!(viewport.window_to_pixmap((399, 249)), (319, 199));
+        assert_eq!(viewport.pixmap_to_window((160, 100)), (200, 125));
+
+        let viewport = Viewport::fit((320, 200), (1000, 500), 2.0);
+        assert_eq!(viewport.x, 100);
+        assert_eq!(viewport.window_to_pixmap((50, 0)), (0, 0));
+        assert_eq!(viewport.pixmap_to_window((0, 0)), (50, 0));
+    }
+
+    #[test]
+    fn fit_degenerate_sizes() {
+        let viewport = Viewport::fit((0, 0), (640, 400), 1.0);
+        assert_eq!((viewport.width, viewport.height), (400, 400));
+        let viewport = Viewport::fit((320, 200), (0, 0), 1.0);
+        assert_eq!((viewport.width, viewport.height), (0, 0));
+        assert_eq!(viewport.window_to_pixmap((0, 0)), (0, 0));
+    }
+
+    #[test]
+    fn copy_rows_with_pitch() {
+        let source = [1, 2, 3, 4, 5, 6];
+        let mut target = [0; 8];
+        copy_rows(&source, 3, &mut target, 4);
+        assert_eq!(target, [1, 2, 3, 0, 4, 5, 6, 0]);
+        let mut target = [0; 6];
+        copy_rows(&source, 3, &mut target, 3);
+        assert_eq!(target, source);
+    }
+}
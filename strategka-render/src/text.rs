@@ -0,0 +1,466 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use tiny_skia::{Color, ColorU8, Pixmap, PixmapPaint, Transform};
+
+/// Columns of a glyph, each column is a byte with the top row in the lowest bit
+pub type Glyph = [u8; BitmapFont::GLYPH_WIDTH as usize];
+
+/// Monospace bitmap font. Only the format of the bundled 5x7 font is supported: every
+/// glyph is a [`Glyph`] of 5 columns and 7 rows, fonts may differ in characters and spacing.
+#[derive(Debug, Copy, Clone)]
+pub struct BitmapFont {
+    /// Glyphs for consecutive characters starting from `first`
+    pub glyphs: &'static [Glyph],
+    pub first: char,
+    /// Character drawn in place of the ones missing in the font
+    pub fallback: char,
+    /// Horizontal advance between glyphs, including the gap
+    pub advance: u32,
+    /// Vertical advance between lines, including the gap
+    pub line_height: u32,
+}
+
+impl Default for BitmapFont {
+    /// Bundled 5x7 font covering printable ASCII
+    fn default() -> Self {
+        BitmapFont {
+            glyphs: &FONT_5X7,
+            first: ' ',
+            fallback: '?',
+            advance: 6,
+            line_height: 9,
+        }
+    }
+}
+
+impl BitmapFont {
+    pub const GLYPH_WIDTH: u32 = 5;
+    pub const GLYPH_HEIGHT: u32 = 7;
+
+    fn columns(&self, c: char) -> &'static Glyph {
+        let index = |c: char| (c as u32).checked_sub(self.first as u32);
+        index(c)
+            .and_then(|i| self.glyphs.get(i as usize))
+            .or_else(|| index(self.fallback).and_then(|i| self.glyphs.get(i as usize)))
+            .unwrap_or(&[0; BitmapFont::GLYPH_WIDTH as usize])
+    }
+
+    fn is_set(&self, c: char, x: u32, y: u32) -> bool {
+        self.columns(c)[x as usize] & (1 << y) != 0
+    }
+}
+
+/// Character, scale and RGBA color of a rasterized glyph
+type GlyphKey = (char, u32, [u8; 4]);
+
+/// Horizontal alignment of lines relative to the drawing position
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Align {
+    /// Position is the left edge of the text
+    #[default]
+    Left,
+    /// Position is the center of the text
+    Center,
+    /// Position is the right edge of the text
+    Right,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextStyle {
+    pub color: Color,
+    /// Integer scale of the glyphs, bitmap fonts look blurry otherwise
+    pub scale: u32,
+    pub align: Align,
+    /// Maximum width of a line in pixels. Longer lines are wrapped at spaces, or at any
+    /// character if a single word doesn't fit.
+    pub wrap: Option<u32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            color: Color::WHITE,
+            scale: 1,
+            align: Align::Left,
+            wrap: None,
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn new(color: Color) -> Self {
+        TextStyle {
+            color,
+            ..TextStyle::default()
+        }
+    }
+
+    pub fn scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn wrap(mut self, width: u32) -> Self {
+        self.wrap = Some(width);
+        self
+    }
+}
+
+/// Draws text into pixmaps with a [`BitmapFont`]. Rasterized glyphs are cached per
+/// character, scale and color, so drawing the same HUD every frame is cheap.
+///
+/// Drawing takes `&self` so the renderer can be shared by `render` closures that only
+/// borrow their environment.
+#[derive(Debug, Default)]
+pub struct TextRenderer {
+    pub font: BitmapFont,
+    cache: RefCell<HashMap<GlyphKey, Option<Pixmap>>>,
+}
+
+impl TextRenderer {
+    pub fn new(font: BitmapFont) -> Self {
+        TextRenderer {
+            font,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Height of a single line in pixels
+    pub fn line_height(&self, style: &TextStyle) -> u32 {
+        self.font.line_height * style.scale
+    }
+
+    /// Width of the line in pixels, without wrapping
+    pub fn line_width(&self, line: &str, style: &TextStyle) -> u32 {
+        let count = line.chars().count() as u32;
+        if count == 0 {
+            return 0;
+        }
+        ((count - 1) * self.font.advance + BitmapFont::GLYPH_WIDTH) * style.scale
+    }
+
+    /// Split text into lines at newlines and wrap them to the style width
+    pub fn layout<'a>(&self, text: &'a str, style: &TextStyle) -> Vec<&'a str> {
+        let mut lines = vec![];
+        for line in text.split('\n') {
+            match style.wrap {
+                Some(width) => self.wrap_line(line, width, style, &mut lines),
+                None => lines.push(line),
+            }
+        }
+        lines
+    }
+
+    fn wrap_line<'a>(&self, line: &'a str, width: u32, style: &TextStyle, out: &mut Vec<&'a str>) {
+        let advance = self.font.advance * style.scale;
+        // At least one character per line to always make progress
+        let max_chars = ((width + advance).saturating_sub(BitmapFont::GLYPH_WIDTH * style.scale)
+            / advance)
+            .max(1) as usize;
+        let first = out.len();
+        let mut rest = line;
+        // Byte index of the first character that doesn't fit
+        while let Some((limit, _)) = rest.char_indices().nth(max_chars) {
+            // Break at the last space that fits, the space itself is dropped. Leading
+            // spaces are kept as indentation instead of becoming an empty line.
+            let (end, next) = match rest[..limit].rfind(' ') {
+                _ if rest[limit..].starts_with(' ') => (limit, limit + 1),
+                Some(space) if !rest[..space].trim_end().is_empty() => (space, space + 1),
+                _ => (limit, limit),
+            };
+            out.push(rest[..end].trim_end());
+            rest = rest[next..].trim_start();
+        }
+        // Trailing spaces that were wrapped away don't make an empty line
+        if !rest.is_empty() || out.len() == first {
+            out.push(rest);
+        }
+    }
+
+    /// Size of the text block in pixels
+    pub fn measure(&self, text: &str, style: &TextStyle) -> (u32, u32) {
+        let lines = self.layout(text, style);
+        let width = lines
+            .iter()
+            .map(|line| self.line_width(line, style))
+            .max()
+            .unwrap_or(0);
+        (width, lines.len() as u32 * self.line_height(style))
+    }
+
+    /// Draw text with the top of the first line at `y`. The meaning of `x` depends on
+    /// the style alignment. Returns the size of the drawn block.
+    pub fn draw(
+        &self,
+        pixmap: &mut Pixmap,
+        text: &str,
+        (x, y): (i32, i32),
+        style: &TextStyle,
+    ) -> (u32, u32) {
+        let lines = self.layout(text, style);
+        let mut width = 0;
+        for (i, line) in lines.iter().enumerate() {
+            let line_width = self.line_width(line, style);
+            width = width.max(line_width);
+            let left = match style.align {
+                Align::Left => x,
+                Align::Center => x - line_width as i32 / 2,
+                Align::Right => x - line_width as i32,
+            };
+            let top = y + (i as u32 * self.line_height(style)) as i32;
+            for (j, c) in line.chars().enumerate() {
+                let gx = left + (j as u32 * self.font.advance * style.scale) as i32;
+                self.draw_glyph(pixmap, c, (gx, top), style);
+            }
+        }
+        (width, lines.len() as u32 * self.line_height(style))
+    }
+
+    fn draw_glyph(&self, pixmap: &mut Pixmap, c: char, (x, y): (i32, i32), style: &TextStyle) {
+        if c == ' ' {
+            return;
+        }
+        let color = style.color.to_color_u8();
+        let key = (
+            c,
+            style.scale,
+            [color.red(), color.green(), color.blue(), color.alpha()],
+        );
+        let mut cache = self.cache.borrow_mut();
+        let glyph = cache
+            .entry(key)
+            .or_insert_with(|| self.rasterize(c, style.scale, color));
+        if let Some(glyph) = glyph {
+            pixmap.draw_pixmap(
+                x,
+                y,
+                glyph.as_ref(),
+                &PixmapPaint::default(),
+                Transform::identity(),
+                None,
+            );
+        }
+    }
+
+    fn rasterize(&self, c: char, scale: u32, color: ColorU8) -> Option<Pixmap> {
+        let font = &self.font;
+        let mut glyph = Pixmap::new(
+            BitmapFont::GLYPH_WIDTH * scale,
+            BitmapFont::GLYPH_HEIGHT * scale,
+        )?;
+        let width = glyph.width();
+        let pixel = color.premultiply();
+        let pixels = glyph.pixels_mut();
+        for gy in 0..BitmapFont::GLYPH_HEIGHT {
+            for gx in 0..BitmapFont::GLYPH_WIDTH {
+                if !font.is_set(c, gx, gy) {
+                    continue;
+                }
+                for sy in 0..scale {
+                    let row = (gy * scale + sy) * width;
+                    let start = (row + gx * scale) as usize;
+                    pixels[start..start + scale as usize].fill(pixel);
+                }
+            }
+        }
+        Some(glyph)
+    }
+
+    /// Drop rasterized glyphs, e.g. after changing the font
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+}
+
+/// Printable ASCII from space to tilde
+#[rustfmt::skip]
+static FONT_5X7: [Glyph; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x01, 0x01], // F
+    [0x3E, 0x41, 0x41, 0x51, 0x32], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x7F, 0x20, 0x18, 0x20, 0x7F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x0C, 0x52, 0x52, 0x52, 0x3E], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x00, 0x7F, 0x10, 0x28, 0x44], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x02, 0x01, 0x02, 0x04, 0x02], // ~
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Width of `chars` glyphs of the default font
+    fn width(chars: u32) -> u32 {
+        (chars - 1) * 6 + BitmapFont::GLYPH_WIDTH
+    }
+
+    fn wrapped(text: &str, chars: u32) -> Vec<&str> {
+        let style = TextStyle::default().wrap(width(chars));
+        TextRenderer::default().layout(text, &style)
+    }
+
+    #[test]
+    fn wrap_at_spaces() {
+        assert_eq!(wrapped("hello world", 5), ["hello", "world"]);
+        assert_eq!(wrapped("ab cdefgh", 5), ["ab", "cdefg", "h"]);
+        assert_eq!(wrapped("one two three", 8), ["one two", "three"]);
+        assert_eq!(wrapped("one\n\ntwo", 8), ["one", "", "two"]);
+        assert_eq!(wrapped("", 8), [""]);
+    }
+
+    #[test]
+    fn wrap_long_words() {
+        assert_eq!(wrapped("abcdefghijkl", 5), ["abcde", "fghij", "kl"]);
+        assert_eq!(wrapped("a abcdefgh", 5), ["a", "abcde", "fgh"]);
+        // Width below a single glyph still makes progress
+        let style = TextStyle::default().wrap(1);
+        assert_eq!(
+            TextRenderer::default().layout("ab c", &style),
+            ["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn wrap_leading_and_trailing_spaces() {
+        assert_eq!(wrapped("   abcdef", 5), ["   ab", "cdef"]);
+        assert_eq!(wrapped("  ab cd", 5), ["  ab", "cd"]);
+        assert_eq!(wrapped("abc   ", 5), ["abc"]);
+        assert_eq!(wrapped("abcde      ", 5), ["abcde"]);
+        assert_eq!(wrapped("ab  ", 8), ["ab  "]);
+    }
+
+    #[test]
+    fn wrap_multibyte() {
+        assert_eq!(wrapped("абв где жзий", 5), ["абв", "где", "жзий"]);
+        assert_eq!(wrapped("日本語のテキスト", 3), ["日本語", "のテキ", "スト"]);
+    }
+
+    #[test]
+    fn measure_text() {
+        let text = TextRenderer::default();
+        let style = TextStyle::default();
+        assert_eq!(text.measure("", &style), (0, 9));
+        assert_eq!(text.measure("ab\nжзий", &style), (width(4), 18));
+        assert_eq!(
+            text.measure("ab\nжзий", &style.scale(2)),
+            (width(4) * 2, 36)
+        );
+
+        let style = TextStyle::default().scale(3).wrap(width(5) * 3);
+        assert_eq!(text.measure("hello world", &style), (width(5) * 3, 54));
+        let (w, h) = text.draw(
+            &mut Pixmap::new(100, 100).expect("pixmap"),
+            "hello world",
+            (0, 0),
+            &style,
+        );
+        assert_eq!((w, h), text.measure("hello world", &style));
+    }
+
+    #[test]
+    fn glyph_rows() {
+        let text = TextRenderer::default();
+        let style = TextStyle::new(Color::WHITE).scale(2);
+        let mut pixmap = Pixmap::new(20, 20).expect("pixmap");
+        text.draw(&mut pixmap, "_", (0, 0), &style);
+        // Underscore is the bottom row only, the top row is the lowest bit
+        let row = |y: u32| pixmap.pixel(0, y).expect("pixel").alpha();
+        assert_eq!(row(0), 0);
+        assert_eq!(row(11), 0);
+        assert_eq!(row(12), 255);
+        assert_eq!(row(13), 255);
+        assert_eq!(row(14), 0);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::text::{Align, TextStyle};
+use crate::ui::{fill_rect, UiFrame};
+use strategka_core::{Replay, Turn, World};
+use tiny_skia::{Color, Rect};
+
+/// Maximum amount of inputs listed in the tooltip of a marker
+const TOOLTIP_INPUTS: usize = 8;
+
+/// Scrub bar of [`crate::replay_loop`] along the bottom of the frame. Shows the current turn
+/// and marks turns that have recorded inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    /// Height of the bar in pixels
+    pub height: u32,
+    /// Space between the window edges and the first and last turns
+    pub margin: u32,
+    pub background: Color,
+    /// Part of the bar that is already played
+    pub progress: Color,
+    pub cursor: Color,
+    /// Markers of inputs without known player
+    pub marker: Color,
+    /// Marker colors by player index, repeated if there are more players
+    pub players: Vec<Color>,
+    pub text: TextStyle,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Timeline {
+            height: 20,
+            margin: 8,
+            background: Color::from_rgba8(20, 20, 25, 200),
+            progress: Color::from_rgba8(50, 80, 100, 220),
+            cursor: Color::WHITE,
+            marker: Color::from_rgba8(200, 200, 200, 255),
+            players: vec![
+                Color::from_rgba8(230, 80, 70, 255),
+                Color::from_rgba8(70, 140, 230, 255),
+                Color::from_rgba8(90, 200, 90, 255),
+                Color::from_rgba8(230, 200, 60, 255),
+                Color::from_rgba8(180, 90, 220, 255),
+                Color::from_rgba8(60, 210, 210, 255),
+            ],
+            text: TextStyle::new(Color::from_rgba8(230, 230, 230, 255)),
+        }
+    }
+}
+
+impl Timeline {
+    /// Area of the bar in the pixmap of the given size
+    pub fn rect(&self, (width, height): (u32, u32)) -> Option<Rect> {
+        Rect::from_xywh(
+            0.0,
+            height.saturating_sub(self.height) as f32,
+            width as f32,
+            self.height as f32,
+        )
+    }
+
+    /// Horizontal position of the turn on the bar
+    pub fn turn_x(&self, rect: Rect, total: Turn, turn: Turn) -> f32 {
+        let margin = self.margin as f32;
+        let width = (rect.width() - 2.0 * margin).max(1.0);
+        rect.x() + margin + width * turn.min(total) as f32 / total.max(1) as f32
+    }
+
+    /// Turn under the horizontal position on the bar
+    pub fn turn_at(&self, rect: Rect, total: Turn, x: f32) -> Turn {
+        let margin = self.margin as f32;
+        let width = (rect.width() - 2.0 * margin).max(1.0);
+        let fraction = ((x - rect.x() - margin) / width).clamp(0.0, 1.0);
+        (fraction * total as f32).round() as Turn
+    }
+
+    fn player_color(&self, player: Option<usize>) -> Color {
+        match player {
+            Some(i) if !self.players.is_empty() => self.players[i % self.players.len()],
+            _ => self.marker,
+        }
+    }
+
+    /// Draw the timeline for the replay at `turn`. `player` tells which player made the
+    /// input to color the markers. Returns the turn to seek to while the bar is pressed or
+    /// dragged.
+    pub fn show<W, P>(
+        &self,
+        ui: &mut UiFrame,
+        replay: &Replay<W>,
+        turn: Turn,
+        player: P,
+    ) -> Option<Turn>
+    where
+        W: World,
+        P: Fn(&W::Input) -> Option<usize>,
+    {
+        let size = (ui.pixmap().width(), ui.pixmap().height());
+        let rect = self.rect(size)?;
+        let total = replay.total_turns;
+        let dragged = ui.drag_area("replay timeline", rect);
+
+        fill_rect(ui.pixmap(), rect, self.background);
+        let left = rect.x() + self.margin as f32;
+        let cursor = self.turn_x(rect, total, turn);
+        if let Some(played) = Rect::from_xywh(left, rect.y(), cursor - left, rect.height()) {
+            fill_rect(ui.pixmap(), played, self.progress);
+        }
+
+        let (top, height) = (rect.y() + 3.0, rect.height() - 6.0);
+        for (input_turn, inputs) in replay.inputs.iter().filter(|(_, i)| !i.is_empty()) {
+            let x = self.turn_x(rect, total, *input_turn);
+            let mut players: Vec<Option<usize>> = vec![];
+            for p in inputs.iter().map(&player) {
+                if !players.contains(&p) {
+                    players.push(p);
+                }
+            }
+            // Turns with inputs of several players are split between their colors
+            let segment = height / players.len() as f32;
+            for (i, p) in players.into_iter().enumerate() {
+                let y = top + i as f32 * segment;
+                if let Some(marker) = Rect::from_xywh(x - 1.0, y, 2.0, segment) {
+                    fill_rect(ui.pixmap(), marker, self.player_color(p));
+                }
+            }
+        }
+        if let Some(line) = Rect::from_xywh(cursor - 1.0, rect.y(), 3.0, rect.height()) {
+            fill_rect(ui.pixmap(), line, self.cursor);
+        }
+
+        let label = format!("{turn}/{total}");
+        let line_height = ui.text().line_height(&self.text) as i32;
+        let pos = (
+            (rect.right() - self.margin as f32) as i32,
+            rect.y() as i32 - line_height,
+        );
+        ui.text_with(&label, pos, &self.text.align(Align::Right));
+
+        if ui.is_hovered(rect) && !dragged {
+            if let Some(tooltip) = self.hovered_inputs(rect, replay, ui.mouse().0 as f32) {
+                ui.tooltip(rect, &tooltip);
+            }
+        }
+        dragged.then(|| self.turn_at(rect, total, ui.mouse().0 as f32))
+    }
+
+    /// Debug form of the inputs of the marker nearest to the cursor
+    fn hovered_inputs<W: World>(&self, rect: Rect, replay: &Replay<W>, x: f32) -> Option<String> {
+        let total = replay.total_turns;
+        let (turn, inputs) = replay
+            .inputs
+            .iter()
+            .filter(|(_, inputs)| !inputs.is_empty())
+            .map(|(t, inputs)| ((self.turn_x(rect, total, *t) - x).abs(), (t, inputs)))
+            .filter(|(distance, _)| *distance <= 4.0)
+            .min_by(|a, b| a.0.total_cmp(&b.0))?
+            .1;
+        let mut lines = vec![format!("Turn {turn}:")];
+        lines.extend(
+            inputs
+                .iter()
+                .take(TOOLTIP_INPUTS)
+                .map(|input| format!("{input:?}")),
+        );
+        if inputs.len() > TOOLTIP_INPUTS {
+            lines.push(format!("and {} more", inputs.len() - TOOLTIP_INPUTS));
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turn_position_roundtrip() {
+        let timeline = Timeline::default();
+        let rect = timeline.rect((216, 100)).expect("rect");
+        assert_eq!(rect.y(), 80.0);
+        for total in [1, 7, 200, 100_000] {
+            for turn in [0, total / 3, total / 2, total] {
+                let x = timeline.turn_x(rect, total, turn);
+                assert_eq!(timeline.turn_at(rect, total, x), turn, "{turn}/{total}");
+            }
+            assert_eq!(timeline.turn_x(rect, total, 0), 8.0);
+            assert_eq!(timeline.turn_x(rect, total, total), 208.0);
+            // Positions over the margins are the first and last turns
+            assert_eq!(timeline.turn_at(rect, total, 0.0), 0);
+            assert_eq!(timeline.turn_at(rect, total, 216.0), total);
+        }
+    }
+
+    #[test]
+    fn empty_replay() {
+        let timeline = Timeline::default();
+        let rect = timeline.rect((216, 100)).expect("rect");
+        assert_eq!(timeline.turn_x(rect, 0, 0), 8.0);
+        assert_eq!(timeline.turn_x(rect, 0, 5), 8.0);
+        for x in [0.0, 8.0, 100.0, 216.0] {
+            assert_eq!(timeline.turn_at(rect, 0, x), 0);
+        }
+    }
+}
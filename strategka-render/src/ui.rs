@@ -0,0 +1,676 @@
+use crate::present::Viewport;
+use crate::text::{Align, TextRenderer, TextStyle};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+/// Identifier of a widget that keeps its state between frames
+pub type WidgetId = u64;
+
+/// Make widget id from a string that is unique within the frame
+pub fn widget_id(label: &str) -> WidgetId {
+    let mut hasher = DefaultHasher::new();
+    label.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Colors and text style of widgets
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UiStyle {
+    pub text: TextStyle,
+    pub panel: Color,
+    pub widget: Color,
+    /// Widget under the cursor
+    pub hovered: Color,
+    /// Widget that is pressed or dragged
+    pub active: Color,
+    /// Selected list rows and filled part of sliders
+    pub accent: Color,
+    pub tooltip: Color,
+    /// Space between borders of widgets and their content
+    pub padding: u32,
+}
+
+impl Default for UiStyle {
+    fn default() -> Self {
+        UiStyle {
+            text: TextStyle::new(Color::from_rgba8(230, 230, 230, 255)),
+            panel: Color::from_rgba8(30, 30, 40, 220),
+            widget: Color::from_rgba8(60, 60, 75, 255),
+            hovered: Color::from_rgba8(80, 80, 100, 255),
+            active: Color::from_rgba8(100, 100, 130, 255),
+            accent: Color::from_rgba8(50, 127, 150, 255),
+            tooltip: Color::from_rgba8(10, 10, 10, 230),
+            padding: 4,
+        }
+    }
+}
+
+/// Immediate mode UI maintained by the render loop. Widgets are declared every frame by
+/// [`crate::View::ui`] and report interactions right away.
+///
+/// Mouse presses over widgets of the previous frame are consumed by the UI together with
+/// their releases, as are arrow keys while a widget is focused. Consumed events don't
+/// reach the event handler, so clicks on UI don't become world inputs.
+#[derive(Debug, Default)]
+pub struct Ui {
+    pub style: UiStyle,
+    pub text: TextRenderer,
+    /// Cursor position in pixmap coordinates
+    mouse: (i32, i32),
+    /// Left button went down over UI since the last frame
+    pressed: bool,
+    /// Left button went up since the last frame
+    released: bool,
+    /// Wheel steps over UI since the last frame
+    wheel: i32,
+    /// Arrow keys pressed while a widget is focused
+    keys: Vec<Keycode>,
+    /// Mouse buttons pressed over UI, their releases are consumed too
+    held: HashSet<MouseButton>,
+    /// Widget that is pressed or dragged
+    active: Option<WidgetId>,
+    /// Widget that receives arrow keys
+    focused: Option<WidgetId>,
+    focus_declared: bool,
+    /// Areas covered by widgets in the last frame
+    areas: Vec<Rect>,
+    next_areas: Vec<Rect>,
+    /// Scroll position of lists
+    scroll: HashMap<WidgetId, usize>,
+    tooltip: Option<String>,
+}
+
+impl Ui {
+    pub fn new(style: UiStyle) -> Self {
+        Ui {
+            style,
+            ..Ui::default()
+        }
+    }
+
+    /// Check that the pixmap point is covered by widgets of the last frame
+    pub fn is_over(&self, (x, y): (i32, i32)) -> bool {
+        self.areas.iter().any(|r| contains(r, (x, y)))
+    }
+
+    /// Update input state of the UI. Returns `true` if the event is consumed.
+    pub fn handle_event(&mut self, event: &Event, viewport: &Viewport) -> bool {
+        match *event {
+            Event::MouseMotion { x, y, .. } => {
+                self.mouse = viewport.window_to_pixmap((x, y));
+                false
+            }
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => {
+                self.mouse = viewport.window_to_pixmap((x, y));
+                if !self.is_over(self.mouse) {
+                    self.focused = None;
+                    return false;
+                }
+                self.held.insert(mouse_btn);
+                if mouse_btn == MouseButton::Left {
+                    self.pressed = true;
+                }
+                true
+            }
+            Event::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => {
+                self.mouse = viewport.window_to_pixmap((x, y));
+                if !self.held.remove(&mouse_btn) {
+                    return false;
+                }
+                if mouse_btn == MouseButton::Left {
+                    self.released = true;
+                }
+                true
+            }
+            Event::MouseWheel { y, .. } if self.is_over(self.mouse) => {
+                self.wheel += y;
+                true
+            }
+            Event::KeyDown {
+                keycode: Some(key @ (Keycode::Up | Keycode::Down | Keycode::Left | Keycode::Right)),
+                ..
+            } if self.focused.is_some() => {
+                self.keys.push(key);
+                true
+            }
+            // Releases are not delivered to unfocused window
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => {
+                self.held.clear();
+                self.active = None;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Start declaring widgets of the frame that are drawn into the pixmap
+    pub fn begin<'a>(&'a mut self, pixmap: &'a mut Pixmap) -> UiFrame<'a> {
+        self.focus_declared = false;
+        self.tooltip = None;
+        UiFrame { ui: self, pixmap }
+    }
+
+    fn finish(&mut self, pixmap: &mut Pixmap) {
+        if let Some(text) = self.tooltip.take() {
+            let style = self.style.text;
+            let (width, height) = self.text.measure(&text, &style);
+            let padding = self.style.padding;
+            let (w, h) = (width + 2 * padding, height + 2 * padding);
+            // Keep the tooltip inside the pixmap, preferring below right of the cursor
+            let x = (self.mouse.0 + 12)
+                .min(pixmap.width() as i32 - w as i32)
+                .max(0);
+            let y = (self.mouse.1 + 16)
+                .min(pixmap.height() as i32 - h as i32)
+                .max(0);
+            fill(pixmap, (x, y, w, h), self.style.tooltip);
+            self.text.draw(
+                pixmap,
+                &text,
+                (x + padding as i32, y + padding as i32),
+                &style,
+            );
+        }
+        self.areas = std::mem::take(&mut self.next_areas);
+        if self.released {
+            self.active = None;
+        }
+        if !self.focus_declared {
+            self.focused = None;
+        }
+        self.pressed = false;
+        self.released = false;
+        self.wheel = 0;
+        self.keys.clear();
+    }
+}
+
+/// Widgets of a single frame. The frame finishes when dropped, tooltips are drawn then
+/// on top of everything.
+pub struct UiFrame<'a> {
+    ui: &'a mut Ui,
+    pixmap: &'a mut Pixmap,
+}
+
+impl<'a> Drop for UiFrame<'a> {
+    fn drop(&mut self) {
+        self.ui.finish(self.pixmap);
+    }
+}
+
+impl<'a> UiFrame<'a> {
+    /// Pixmap to draw custom widgets
+    pub fn pixmap(&mut self) -> &mut Pixmap {
+        self.pixmap
+    }
+
+    pub fn style(&self) -> &UiStyle {
+        &self.ui.style
+    }
+
+    pub fn text(&self) -> &TextRenderer {
+        &self.ui.text
+    }
+
+    /// Cursor position in pixmap coordinates
+    pub fn mouse(&self) -> (i32, i32) {
+        self.ui.mouse
+    }
+
+    pub fn is_hovered(&self, rect: Rect) -> bool {
+        contains(&rect, self.ui.mouse)
+    }
+
+    /// Mark the area as part of the UI, so mouse presses over it are consumed
+    pub fn block(&mut self, rect: Rect) {
+        self.ui.next_areas.push(rect);
+    }
+
+    /// Background for a group of widgets that also blocks clicks to the world
+    pub fn panel(&mut self, rect: Rect) {
+        self.block(rect);
+        fill_rect(self.pixmap, rect, self.ui.style.panel);
+    }
+
+    /// Text with the top left corner at the point. Returns the size of the text.
+    pub fn label(&mut self, text: &str, pos: (i32, i32)) -> (u32, u32) {
+        self.ui
+            .text
+            .draw(self.pixmap, text, pos, &self.ui.style.text)
+    }
+
+    /// Text with custom style, the meaning of position depends on the style alignment
+    pub fn text_with(&mut self, text: &str, pos: (i32, i32), style: &TextStyle) -> (u32, u32) {
+        self.ui.text.draw(self.pixmap, text, pos, style)
+    }
+
+    /// Show the text near the cursor while it hovers the area
+    pub fn tooltip(&mut self, rect: Rect, text: &str) {
+        if self.is_hovered(rect) {
+            self.ui.tooltip = Some(text.to_owned());
+        }
+    }
+
+    /// Handle press and release of the widget. Returns `true` when the widget is
+    /// clicked, that is released while hovered after being pressed.
+    fn interact(&mut self, id: WidgetId, rect: Rect) -> bool {
+        self.block(rect);
+        let hovered = self.is_hovered(rect);
+        if hovered && self.ui.pressed {
+            self.ui.active = Some(id);
+        }
+        self.ui.released && hovered && self.ui.active == Some(id)
+    }
+
+    fn widget_color(&self, id: WidgetId, rect: Rect) -> Color {
+        if self.ui.active == Some(id) {
+            self.ui.style.active
+        } else if self.is_hovered(rect) {
+            self.ui.style.hovered
+        } else {
+            self.ui.style.widget
+        }
+    }
+
+    fn focus(&mut self, id: WidgetId) -> bool {
+        if self.ui.active == Some(id) {
+            self.ui.focused = Some(id);
+        }
+        let focused = self.ui.focused == Some(id);
+        self.ui.focus_declared |= focused;
+        focused
+    }
+
+    /// Area that is pressed and dragged by mouse. Returns `true` while it is held.
+    pub fn drag_area(&mut self, id: &str, rect: Rect) -> bool {
+        let id = widget_id(id);
+        self.interact(id, rect);
+        self.ui.active == Some(id)
+    }
+
+    /// Button with centered label. Returns `true` when clicked.
+    ///
+    /// The label identifies the button, use [`UiFrame::button_with_id`] for buttons with
+    /// the same label.
+    pub fn button(&mut self, label: &str, rect: Rect) -> bool {
+        self.button_with_id(label, label, rect)
+    }
+
+    /// Button with centered label that is identified by `id` unique within the frame.
+    /// Returns `true` when clicked.
+    pub fn button_with_id(&mut self, id: &str, label: &str, rect: Rect) -> bool {
+        let id = widget_id(id);
+        let clicked = self.interact(id, rect);
+        fill_rect(self.pixmap, rect, self.widget_color(id, rect));
+        let style = self.ui.style.text.align(Align::Center);
+        let (_, height) = self.ui.text.measure(label, &style);
+        let center = (
+            (rect.x() + rect.width() / 2.0) as i32,
+            (rect.y() + (rect.height() - height as f32) / 2.0) as i32,
+        );
+        self.ui.text.draw(self.pixmap, label, center, &style);
+        clicked
+    }
+
+    /// Horizontal slider that is dragged by mouse or moved by arrow keys when focused.
+    /// Returns `true` when the value is changed.
+    pub fn slider(
+        &mut self,
+        id: &str,
+        rect: Rect,
+        value: &mut f32,
+        range: RangeInclusive<f32>,
+    ) -> bool {
+        let id = widget_id(id);
+        self.interact(id, rect);
+        let (min, max) = (*range.start(), *range.end());
+        let old = *value;
+        if self.ui.active == Some(id) {
+            let fraction = (self.ui.mouse.0 as f32 - rect.x()) / rect.width();
+            *value = min + fraction.clamp(0.0, 1.0) * (max - min);
+        }
+        if self.focus(id) {
+            let step = (max - min) / 20.0;
+            for key in self.ui.keys.iter() {
+                match key {
+                    Keycode::Left => *value -= step,
+                    Keycode::Right => *value += step,
+                    _ => (),
+                }
+            }
+        }
+        *value = value.clamp(min, max);
+
+        fill_rect(self.pixmap, rect, self.widget_color(id, rect));
+        let fraction = if max > min {
+            (*value - min) / (max - min)
+        } else {
+            0.0
+        };
+        if let Some(filled) = Rect::from_xywh(
+            rect.x(),
+            rect.y(),
+            (rect.width() * fraction).max(1.0),
+            rect.height(),
+        ) {
+            fill_rect(self.pixmap, filled, self.ui.style.accent);
+        }
+        *value != old
+    }
+
+    /// List of selectable rows that scrolls with mouse wheel. Arrow keys move the selection
+    /// when the list is focused. Returns `true` when the selection is changed.
+    pub fn list<S: AsRef<str>>(
+        &mut self,
+        id: &str,
+        rect: Rect,
+        items: &[S],
+        selected: &mut Option<usize>,
+    ) -> bool {
+        let id = widget_id(id);
+        let clicked = self.interact(id, rect);
+        let old = *selected;
+        let padding = self.ui.style.padding;
+        let row_height = (self.ui.text.line_height(&self.ui.style.text) + padding) as f32;
+        let rows = ((rect.height() / row_height) as usize).max(1);
+        let max_scroll = items.len().saturating_sub(rows);
+
+        if clicked {
+            let row = ((self.ui.mouse.1 as f32 - rect.y()) / row_height) as usize;
+            let index = row + self.ui.scroll.get(&id).copied().unwrap_or(0);
+            if index < items.len() {
+                *selected = Some(index);
+            }
+        }
+        if self.focus(id) && !items.is_empty() {
+            for key in self.ui.keys.iter() {
+                *selected = match (key, *selected) {
+                    (Keycode::Up, Some(i)) => Some(i.saturating_sub(1)),
+                    (Keycode::Down, Some(i)) => Some((i + 1).min(items.len() - 1)),
+                    (Keycode::Up | Keycode::Down, None) => Some(0),
+                    (_, current) => current,
+                };
+            }
+        }
+        let wheel = if self.is_hovered(rect) {
+            self.ui.wheel
+        } else {
+            0
+        };
+        let scroll = self.ui.scroll.entry(id).or_insert(0);
+        if wheel != 0 {
+            *scroll = scroll.saturating_add_signed(-wheel as isize);
+        }
+        // Keep the selection moved by keys visible
+        if *selected != old {
+            if let Some(i) = *selected {
+                *scroll = (*scroll).min(i).max((i + 1).saturating_sub(rows));
+            }
+        }
+        *scroll = (*scroll).min(max_scroll);
+        let scroll = *scroll;
+
+        fill_rect(self.pixmap, rect, self.ui.style.widget);
+        for (row, (i, item)) in items.iter().enumerate().skip(scroll).take(rows).enumerate() {
+            let top = rect.y() + row as f32 * row_height;
+            if *selected == Some(i) {
+                if let Some(row_rect) = Rect::from_xywh(rect.x(), top, rect.width(), row_height) {
+                    fill_rect(self.pixmap, row_rect, self.ui.style.accent);
+                }
+            }
+            let pos = (
+                (rect.x() + padding as f32) as i32,
+                (top + padding as f32 / 2.0) as i32,
+            );
+            self.ui
+                .text
+                .draw(self.pixmap, item.as_ref(), pos, &self.ui.style.text);
+        }
+        *selected != old
+    }
+}
+
+fn contains(rect: &Rect, (x, y): (i32, i32)) -> bool {
+    let (x, y) = (x as f32, y as f32);
+    x >= rect.left() && x < rect.right() && y >= rect.top() && y < rect.bottom()
+}
+
+pub(crate) fn fill_rect(pixmap: &mut Pixmap, rect: Rect, color: Color) {
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+}
+
+fn fill(pixmap: &mut Pixmap, (x, y, w, h): (i32, i32, u32, u32), color: Color) {
+    if let Some(rect) = Rect::from_xywh(x as f32, y as f32, w as f32, h as f32) {
+        fill_rect(pixmap, rect, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mouse_button(down: bool, (x, y): (i32, i32)) -> Event {
+        if down {
+            Event::MouseButtonDown {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: MouseButton::Left,
+                clicks: 1,
+                x,
+                y,
+            }
+        } else {
+            Event::MouseButtonUp {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mouse_btn: MouseButton::Left,
+                clicks: 1,
+                x,
+                y,
+            }
+        }
+    }
+
+    fn mouse_motion((x, y): (i32, i32)) -> Event {
+        Event::MouseMotion {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mousestate: sdl2::mouse::MouseState::from_sdl_state(0),
+            x,
+            y,
+            xrel: 0,
+            yrel: 0,
+        }
+    }
+
+    fn key_down(key: Keycode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(key),
+            scancode: None,
+            keymod: sdl2::keyboard::Mod::empty(),
+            repeat: false,
+        }
+    }
+
+    fn viewport() -> Viewport {
+        Viewport::fit((100, 100), (100, 100), 1.0)
+    }
+
+    #[test]
+    fn press_and_release_are_consumed_in_pairs() {
+        let mut ui = Ui::default();
+        let mut pixmap = Pixmap::new(100, 100).expect("pixmap");
+        let panel = Rect::from_xywh(0.0, 0.0, 50.0, 50.0).expect("rect");
+        ui.begin(&mut pixmap).panel(panel);
+        let viewport = viewport();
+
+        // Release outside of the UI belongs to the press over it
+        assert!(ui.handle_event(&mouse_button(true, (10, 10)), &viewport));
+        assert!(!ui.handle_event(&mouse_motion((80, 80)), &viewport));
+        assert!(ui.handle_event(&mouse_button(false, (80, 80)), &viewport));
+        // Release over the UI belongs to the press that reached the world
+        assert!(!ui.handle_event(&mouse_button(true, (80, 80)), &viewport));
+        assert!(!ui.handle_event(&mouse_button(false, (10, 10)), &viewport));
+
+        let down = Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Right,
+            clicks: 1,
+            x: 10,
+            y: 10,
+        };
+        assert!(ui.handle_event(&down, &viewport));
+        let up = Event::MouseButtonUp {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Right,
+            clicks: 1,
+            x: 90,
+            y: 90,
+        };
+        assert!(ui.handle_event(&up, &viewport));
+        assert!(!ui.handle_event(&up, &viewport));
+
+        // Presses are forgotten when the window loses focus, so nothing gets stuck
+        assert!(ui.handle_event(&mouse_button(true, (10, 10)), &viewport));
+        let focus_lost = Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event: WindowEvent::FocusLost,
+        };
+        assert!(!ui.handle_event(&focus_lost, &viewport));
+        assert!(!ui.handle_event(&mouse_button(false, (10, 10)), &viewport));
+    }
+
+    #[test]
+    fn slider_value() {
+        let mut ui = Ui::default();
+        let mut pixmap = Pixmap::new(200, 100).expect("pixmap");
+        let viewport = Viewport::fit((200, 100), (200, 100), 1.0);
+        let rect = Rect::from_xywh(10.0, 10.0, 100.0, 10.0).expect("rect");
+        let mut value = 20.0;
+        let mut frame = |ui: &mut Ui, value: &mut f32| {
+            ui.begin(&mut pixmap)
+                .slider("volume", rect, value, 0.0..=10.0)
+        };
+
+        // Values out of range are clamped
+        assert!(frame(&mut ui, &mut value));
+        assert_eq!(value, 10.0);
+
+        ui.handle_event(&mouse_button(true, (60, 15)), &viewport);
+        assert!(frame(&mut ui, &mut value));
+        assert_eq!(value, 5.0);
+        ui.handle_event(&mouse_motion((35, 40)), &viewport);
+        assert!(frame(&mut ui, &mut value));
+        assert_eq!(value, 2.5);
+        // Dragging past the ends stops at them
+        ui.handle_event(&mouse_motion((150, 15)), &viewport);
+        frame(&mut ui, &mut value);
+        assert_eq!(value, 10.0);
+        ui.handle_event(&mouse_motion((0, 15)), &viewport);
+        frame(&mut ui, &mut value);
+        assert_eq!(value, 0.0);
+        ui.handle_event(&mouse_button(false, (0, 15)), &viewport);
+        assert!(!frame(&mut ui, &mut value));
+
+        // Dragged slider is focused and moved by arrow keys
+        assert!(ui.handle_event(&key_down(Keycode::Left), &viewport));
+        assert!(!frame(&mut ui, &mut value));
+        assert_eq!(value, 0.0);
+        assert!(ui.handle_event(&key_down(Keycode::Right), &viewport));
+        assert!(ui.handle_event(&key_down(Keycode::Right), &viewport));
+        assert!(frame(&mut ui, &mut value));
+        assert_eq!(value, 1.0);
+    }
+
+    #[test]
+    fn list_selection() {
+        let mut ui = Ui::default();
+        let mut pixmap = Pixmap::new(100, 100).expect("pixmap");
+        let viewport = viewport();
+        // Rows are a line of text with padding high, two of them fit
+        let rect = Rect::from_xywh(0.0, 0.0, 50.0, 26.0).expect("rect");
+        let items = ["a", "b", "c", "d"];
+        let mut selected = None;
+        let mut frame = |ui: &mut Ui, selected: &mut Option<usize>| {
+            ui.begin(&mut pixmap).list("items", rect, &items, selected)
+        };
+
+        assert!(!frame(&mut ui, &mut selected));
+        // Arrow keys go to the world until the list is focused
+        assert!(!ui.handle_event(&key_down(Keycode::Down), &viewport));
+        ui.handle_event(&mouse_button(true, (5, 15)), &viewport);
+        ui.handle_event(&mouse_button(false, (5, 15)), &viewport);
+        assert!(frame(&mut ui, &mut selected));
+        assert_eq!(selected, Some(1));
+
+        for _ in 0..3 {
+            assert!(ui.handle_event(&key_down(Keycode::Down), &viewport));
+        }
+        assert!(frame(&mut ui, &mut selected));
+        assert_eq!(selected, Some(3));
+        // The list is scrolled to keep the selection visible
+        assert_eq!(ui.scroll[&widget_id("items")], 2);
+        assert!(ui.handle_event(&key_down(Keycode::Up), &viewport));
+        assert!(frame(&mut ui, &mut selected));
+        assert_eq!(selected, Some(2));
+
+        // Press outside of the UI takes the focus away
+        assert!(!ui.handle_event(&mouse_button(true, (80, 80)), &viewport));
+        assert!(!ui.handle_event(&key_down(Keycode::Up), &viewport));
+        assert!(!frame(&mut ui, &mut selected));
+        assert_eq!(selected, Some(2));
+    }
+
+    #[test]
+    fn buttons_with_same_label() {
+        let mut ui = Ui::default();
+        let mut pixmap = Pixmap::new(100, 100).expect("pixmap");
+        let viewport = Viewport::fit((100, 100), (100, 100), 1.0);
+        let first = Rect::from_xywh(0.0, 0.0, 40.0, 20.0).expect("rect");
+        let second = Rect::from_xywh(0.0, 50.0, 40.0, 20.0).expect("rect");
+        let mut frame = |ui: &mut Ui| {
+            let mut frame = ui.begin(&mut pixmap);
+            (
+                frame.button_with_id("first", "OK", first),
+                frame.button_with_id("second", "OK", second),
+            )
+        };
+
+        assert_eq!(frame(&mut ui), (false, false));
+        assert!(ui.handle_event(&mouse_button(true, (10, 60)), &viewport));
+        assert_eq!(frame(&mut ui), (false, false));
+        assert_eq!(ui.active, Some(widget_id("second")));
+        assert!(ui.handle_event(&mouse_button(false, (10, 60)), &viewport));
+        assert_eq!(frame(&mut ui), (false, true));
+        assert_eq!(ui.active, None);
+
+        // Clicks outside of widgets reach the world
+        assert!(!ui.handle_event(&mouse_button(true, (80, 80)), &viewport));
+        assert!(!ui.handle_event(&mouse_button(false, (80, 80)), &viewport));
+    }
+}
@@ -0,0 +1,221 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+use strategka_core::{Playback, Replay, SaveGame, Tick, Turn, World};
+
+use crate::state_hash;
+
+/// Run simulation of the whole replay and return the final state. Inputs at
+/// the last turn are applied, but the turn is not simulated, the same as the
+/// replay playback ends.
+pub fn simulate_replay<W, I, S, E>(replay: &Replay<W>, input_handler: I, simulate: S) -> W
+where
+    W: World + Clone,
+    I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+    S: FnMut(&mut W, Tick) -> Result<(), E>,
+    E: Debug,
+{
+    let mut playback = Playback::new(replay);
+    playback
+        .advance_to(Turn::MAX, input_handler, simulate)
+        .expect("replay playback failed");
+    playback.into_state()
+}
+
+/// Check that the same inputs yield the same state. The simulation is run
+/// twice from the initial state and once more with the state passed through
+/// serialization in the middle, which catches state that is not saved.
+/// Turns are simulated at the given rate.
+pub fn assert_deterministic<W, I, S, E>(
+    initial: &W,
+    rate: u32,
+    inputs: &[(Turn, Vec<W::Input>)],
+    turns: Turn,
+    mut input_handler: I,
+    mut simulate: S,
+) where
+    W: World + Default + Clone + PartialEq + Debug + Serialize + DeserializeOwned,
+    I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+    S: FnMut(&mut W, Tick) -> Result<(), E>,
+    E: Debug,
+{
+    let mut replay = Replay::new(initial, rate);
+    for (turn, turn_inputs) in inputs {
+        replay
+            .record(*turn, turn_inputs)
+            .expect("inputs turns must be strictly increasing");
+    }
+    replay.total_turns = replay.total_turns.max(turns);
+
+    let first = simulate_replay(&replay, &mut input_handler, &mut simulate);
+    let second = simulate_replay(&replay, &mut input_handler, &mut simulate);
+    assert_eq!(first, second, "Simulation is not deterministic");
+    assert_eq!(
+        state_hash(&first),
+        state_hash(&second),
+        "Serialized states differ while the states are equal"
+    );
+
+    let middle = replay.total_turns / 2;
+    let mut playback = Playback::new(&replay);
+    playback
+        .advance_to(middle, &mut input_handler, &mut simulate)
+        .expect("replay playback failed");
+    if !playback.is_stopped() {
+        let state = roundtrip(playback.state());
+        playback = Playback::resume(&replay, state, middle);
+        playback
+            .advance_to(Turn::MAX, &mut input_handler, &mut simulate)
+            .expect("replay playback failed");
+    }
+    let third = playback.into_state();
+    assert_eq!(
+        first, third,
+        "Simulation diverges after the state is saved and loaded at turn {middle}"
+    );
+}
+
+/// Check that encoding of the world is lossless, both as plain CBOR and
+/// inside [`SaveGame`] container with the given simulation rate.
+pub fn assert_world_roundtrip<W>(world: &W, rate: u32)
+where
+    W: World + Default + Clone + PartialEq + Debug + Serialize + DeserializeOwned,
+{
+    assert_eq!(
+        &roundtrip(world),
+        world,
+        "World changed after CBOR round trip"
+    );
+
+    let save = SaveGame::new(world, rate);
+    let mut buffer = vec![];
+    save.encode(&mut buffer).expect("save encoded");
+    let decoded = SaveGame::<W>::decode(&buffer).expect("save decoded");
+    assert_eq!(&decoded.world, world, "World changed after save round trip");
+}
+
+fn roundtrip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+    let mut buffer = vec![];
+    ciborium::into_writer(value, &mut buffer).expect("encoded");
+    ciborium::from_reader(&buffer[..]).expect("decoded")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::convert::Infallible;
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    pub struct CounterWorld {
+        pub value: i64,
+        pub ticks: u64,
+        #[serde(skip)]
+        pub cache: u64,
+    }
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub enum CounterInput {
+        Add(i64),
+    }
+
+    impl World for CounterWorld {
+        type Input = CounterInput;
+
+        fn magic_bytes() -> [u8; 4] {
+            *b"TWDT"
+        }
+
+        fn current_version() -> u32 {
+            1
+        }
+    }
+
+    pub fn input_handler(
+        world: &mut CounterWorld,
+        input: &CounterInput,
+    ) -> Result<bool, Infallible> {
+        match input {
+            CounterInput::Add(v) => world.value += v,
+        }
+        Ok(false)
+    }
+
+    pub fn simulate(world: &mut CounterWorld, _: Tick) -> Result<(), Infallible> {
+        world.ticks += 1;
+        Ok(())
+    }
+
+    fn inputs() -> Vec<(Turn, Vec<CounterInput>)> {
+        vec![
+            (1, vec![CounterInput::Add(1)]),
+            (5, vec![CounterInput::Add(2), CounterInput::Add(3)]),
+        ]
+    }
+
+    #[test]
+    fn deterministic_world() {
+        assert_deterministic(
+            &CounterWorld::default(),
+            30,
+            &inputs(),
+            10,
+            input_handler,
+            simulate,
+        );
+        assert_world_roundtrip(
+            &CounterWorld {
+                value: 4,
+                ticks: 2,
+                cache: 0,
+            },
+            30,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not deterministic")]
+    fn non_deterministic_world() {
+        let mut runs = 0;
+        assert_deterministic(
+            &CounterWorld::default(),
+            30,
+            &inputs(),
+            10,
+            input_handler,
+            |world: &mut CounterWorld, tick| {
+                runs += 1;
+                world.ticks += tick.turn + runs / 11;
+                Ok::<_, Infallible>(())
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "saved and loaded")]
+    fn unsaved_state() {
+        assert_deterministic(
+            &CounterWorld::default(),
+            30,
+            &inputs(),
+            10,
+            input_handler,
+            |world: &mut CounterWorld, _| {
+                world.cache += 1;
+                world.ticks = world.cache;
+                Ok::<_, Infallible>(())
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "CBOR round trip")]
+    fn lossy_world() {
+        assert_world_roundtrip(
+            &CounterWorld {
+                value: 0,
+                ticks: 0,
+                cache: 1,
+            },
+            30,
+        );
+    }
+}
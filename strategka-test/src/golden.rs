@@ -0,0 +1,162 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+};
+use strategka_core::{error::GenericError, Replay, Tick, World};
+
+use crate::{simulate_replay, state_hash};
+
+/// Environment variable that makes [`assert_golden_replay`] accept current
+/// results and rewrite snapshots instead of failing.
+pub const UPDATE_GOLDEN_VAR: &str = "STRATEGKA_UPDATE_GOLDEN";
+
+/// Path of the snapshot that is stored next to the replay, e.g.
+/// `level1.replay.hash`. The snapshot contains game version and hash of the
+/// final state.
+pub fn golden_hash_path<P: AsRef<Path>>(replay_path: P) -> PathBuf {
+    let mut path = replay_path.as_ref().as_os_str().to_owned();
+    path.push(".hash");
+    path.into()
+}
+
+/// Save replay and snapshot of its final state to commit it as golden replay
+pub fn record_golden_replay<W, I, S, E, P>(
+    path: P,
+    replay: &Replay<W>,
+    input_handler: I,
+    simulate: S,
+) where
+    W: World + Default + Clone + Serialize + DeserializeOwned,
+    I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+    S: FnMut(&mut W, Tick) -> Result<(), E>,
+    E: Debug,
+    P: AsRef<Path>,
+{
+    replay.save(&path).expect("golden replay saved");
+    let hash = state_hash(&simulate_replay(replay, input_handler, simulate));
+    write_snapshot(golden_hash_path(&path), W::current_version(), hash);
+}
+
+/// Check that committed replay still produces the recorded final state.
+///
+/// Fails when the replay cannot be loaded by the current game version, when
+/// `current_version` was bumped after the snapshot was made and when the
+/// final state changed. Set `STRATEGKA_UPDATE_GOLDEN=1` to accept changes:
+/// the replay is saved with the current version and the snapshot is rewritten.
+pub fn assert_golden_replay<W, I, S, E, P>(path: P, input_handler: I, simulate: S)
+where
+    W: World + Default + Clone + Serialize + DeserializeOwned,
+    I: FnMut(&mut W, &W::Input) -> Result<bool, E>,
+    S: FnMut(&mut W, Tick) -> Result<(), E>,
+    E: Debug,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let replay = match Replay::<W>::load(path) {
+        Ok(replay) => replay,
+        Err(err) => match root_cause(err) {
+            GenericError::UnsupportedGameVersion(version) => panic!(
+                "Golden replay {path:?} has game version {version}, but current version is {} and it doesn't accept the old one. Record the replay again.",
+                W::current_version()
+            ),
+            err => panic!("Failed to load golden replay {path:?}: {err}"),
+        },
+    };
+    if std::env::var_os(UPDATE_GOLDEN_VAR).is_some() {
+        record_golden_replay(path, &replay, input_handler, simulate);
+        return;
+    }
+
+    let snapshot_path = golden_hash_path(path);
+    let (version, expected) = read_snapshot(&snapshot_path).unwrap_or_else(|| {
+        panic!("Missing or malformed snapshot {snapshot_path:?}. Run tests with {UPDATE_GOLDEN_VAR}=1 to create it.")
+    });
+    if version != W::current_version() {
+        panic!(
+            "Game version was bumped from {version} to {} since golden replay {path:?} was recorded. Run tests with {UPDATE_GOLDEN_VAR}=1 to migrate it.",
+            W::current_version()
+        );
+    }
+    let hash = state_hash(&simulate_replay(&replay, input_handler, simulate));
+    if hash != expected {
+        panic!(
+            "Final state of golden replay {path:?} changed: expected hash {expected:016x}, got {hash:016x}. If the change is intended, bump World::current_version and run tests with {UPDATE_GOLDEN_VAR}=1."
+        );
+    }
+}
+
+fn write_snapshot(path: PathBuf, version: u32, hash: u64) {
+    fs::write(&path, format!("{version} {hash:016x}\n")).expect("golden snapshot written");
+}
+
+fn read_snapshot(path: &Path) -> Option<(u32, u64)> {
+    let contents = fs::read_to_string(path).ok()?;
+    let (version, hash) = contents.trim().split_once(' ')?;
+    Some((version.parse().ok()?, u64::from_str_radix(hash, 16).ok()?))
+}
+
+fn root_cause<I: Debug>(err: GenericError<I>) -> GenericError<I> {
+    match err {
+        GenericError::Context(_, inner) => root_cause(*inner),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::determinism::tests::*;
+    use std::convert::Infallible;
+
+    fn golden_replay() -> (temp_file::TempFile, Replay<CounterWorld>) {
+        let mut replay = Replay::new(&CounterWorld::default(), 60);
+        replay.record(2, &[CounterInput::Add(3)]).expect("record");
+        replay.total_turns = 10;
+        let file = temp_file::empty();
+        record_golden_replay(file.path(), &replay, input_handler, simulate);
+        (file, replay)
+    }
+
+    #[test]
+    fn golden_replay_passes() {
+        let (file, _) = golden_replay();
+        assert_golden_replay::<CounterWorld, _, _, _, _>(file.path(), input_handler, simulate);
+        fs::remove_file(golden_hash_path(file.path())).expect("snapshot removed");
+    }
+
+    #[test]
+    fn golden_replay_detects_changes() {
+        let (file, _) = golden_replay();
+        let result = std::panic::catch_unwind(|| {
+            assert_golden_replay::<CounterWorld, _, _, _, _>(
+                file.path(),
+                input_handler,
+                |world: &mut CounterWorld, _| {
+                    world.ticks += 2;
+                    Ok::<_, Infallible>(())
+                },
+            )
+        });
+        fs::remove_file(golden_hash_path(file.path())).expect("snapshot removed");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn golden_replay_detects_version_bump() {
+        let (file, _) = golden_replay();
+        let snapshot = golden_hash_path(file.path());
+        let (version, hash) = read_snapshot(&snapshot).expect("snapshot");
+        write_snapshot(snapshot.clone(), version - 1, hash);
+        let result = std::panic::catch_unwind(|| {
+            assert_golden_replay::<CounterWorld, _, _, _, _>(file.path(), input_handler, simulate)
+        });
+        fs::remove_file(snapshot).expect("snapshot removed");
+        let message = result.expect_err("version bump detected");
+        assert!(message
+            .downcast_ref::<String>()
+            .expect("panic message")
+            .contains("was bumped from 0 to 1"));
+    }
+}
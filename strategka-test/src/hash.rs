@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Stable hash of the state. FNV-1a of CBOR encoding is used, so the value
+/// doesn't depend on platform or compiler version and can be committed.
+pub fn state_hash<T: Serialize>(value: &T) -> u64 {
+    let mut buffer = vec![];
+    ciborium::into_writer(value, &mut buffer).expect("state is serializable to CBOR");
+    buffer.iter().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_hash() {
+        // CBOR of empty string is single byte 0x60
+        assert_eq!(state_hash(&""), (FNV_OFFSET ^ 0x60).wrapping_mul(FNV_PRIME));
+        assert_eq!(state_hash(&(1, "a")), state_hash(&(1, "a")));
+        assert_ne!(state_hash(&(1, "a")), state_hash(&(2, "a")));
+    }
+}
@@ -0,0 +1,9 @@
+//! Helpers for tests of games built on strategka. The simulation is passed
+//! with the same closures as to the render loop.
+pub mod determinism;
+pub mod golden;
+pub mod hash;
+
+pub use determinism::*;
+pub use golden::*;
+pub use hash::*;